@@ -0,0 +1,122 @@
+use crate::{
+    assets::{ensure_dependency, name_from_path, resolve_relative_path},
+    bitmap_font::{BmFont, BmFontFace, BmFontText},
+    context::GameContext,
+    game::GameSubsystem,
+};
+use anput::world::World;
+use keket::{
+    database::{handle::AssetHandle, path::AssetPathStatic},
+    protocol::AssetProtocol,
+};
+use spitfire_glow::renderer::GlowTextureFiltering;
+use std::{error::Error, sync::Arc};
+
+pub struct BmFontAsset {
+    pub font: Arc<BmFont>,
+    /// Texture names of font pages.
+    pub textures: Vec<String>,
+    pub filtering: GlowTextureFiltering,
+}
+
+impl BmFontAsset {
+    pub fn face(&self) -> BmFontFace {
+        BmFontFace {
+            font: self.font.clone(),
+            textures: self.textures.clone(),
+            filtering: self.filtering,
+        }
+    }
+
+    pub fn text(&self, text: impl ToString) -> BmFontText {
+        self.face().text(text)
+    }
+}
+
+pub struct BmFontAssetSubsystem;
+
+impl GameSubsystem for BmFontAssetSubsystem {
+    fn run(&mut self, context: GameContext, _: f32) {
+        for entity in context.assets.storage.added().iter_of::<BmFontAsset>() {
+            if let Some((path, asset)) = context
+                .assets
+                .storage
+                .lookup_one::<true, (&AssetPathStatic, &BmFontAsset)>(entity)
+            {
+                context
+                    .bitmap_fonts
+                    .insert(name_from_path(&path), asset.face());
+            }
+        }
+        for entity in context.assets.storage.removed().iter_of::<BmFontAsset>() {
+            if let Some(path) = context
+                .assets
+                .storage
+                .lookup_one::<true, &AssetPathStatic>(entity)
+            {
+                context.bitmap_fonts.remove(name_from_path(&path));
+            }
+        }
+    }
+}
+
+pub struct BmFontAssetProtocol;
+
+impl AssetProtocol for BmFontAssetProtocol {
+    fn name(&self) -> &str {
+        "bmfont"
+    }
+
+    fn process_bytes(
+        &mut self,
+        handle: AssetHandle,
+        storage: &mut World,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error>> {
+        let path = storage.component::<true, AssetPathStatic>(handle.entity())?;
+        let font = BmFont::parse(&bytes)
+            .map_err(|error| format!("Failed to load bitmap font: {:?}. {}", path.path(), error))?;
+        let filter = path
+            .meta_items()
+            .find(|(key, _)| *key == "filter")
+            .map(|(_, value)| value)
+            .unwrap_or("nearest")
+            .to_owned();
+        let filtering = match filter.as_str() {
+            "nearest" => GlowTextureFiltering::Nearest,
+            "linear" => GlowTextureFiltering::Linear,
+            _ => {
+                return Err(format!(
+                    "Unknown bitmap font filter: `{}` in: {:?}",
+                    filter,
+                    path.path()
+                )
+                .into())
+            }
+        };
+        let textures = font
+            .pages
+            .iter()
+            .map(|page| resolve_relative_path(path.path(), page))
+            .collect::<Vec<_>>();
+        drop(path);
+
+        for texture in &textures {
+            ensure_dependency(
+                storage,
+                handle,
+                AssetPathStatic::new(format!("texture://{}?filter={}", texture, filter)),
+            )?;
+        }
+        storage.insert(
+            handle.entity(),
+            (BmFontAsset {
+                font: Arc::new(font),
+                textures,
+                filtering,
+            },),
+        )?;
+
+        Ok(())
+    }
+}
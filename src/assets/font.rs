@@ -1,11 +1,24 @@
 use crate::{context::GameContext, game::GameSubsystem};
 use anput::world::World;
-use fontdue::Font;
+use fontdue::{
+    layout::{CoordinateSystem, Layout, LayoutSettings, TextStyle},
+    Font,
+};
 use keket::{
     database::{handle::AssetHandle, path::AssetPathStatic},
     protocol::AssetProtocol,
 };
+use spitfire_draw::{
+    context::DrawContext,
+    text::Text,
+    utils::{transform_to_matrix, Drawable, Vertex},
+};
+use spitfire_glow::{
+    graphics::{Graphics, GraphicsBatch},
+    renderer::{GlowBlending, GlowTextureFiltering, GlowUniformValue},
+};
 use std::error::Error;
+use vek::{Rgba, Vec2};
 
 use super::name_from_path;
 
@@ -13,6 +26,174 @@ pub struct FontAsset {
     pub font: Font,
 }
 
+/// Ordered list of font names, where later fonts cover glyphs missing in earlier ones.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FontFallbackChain {
+    pub fonts: Vec<String>,
+}
+
+impl FontFallbackChain {
+    pub fn new(font: impl ToString) -> Self {
+        Self {
+            fonts: vec![font.to_string()],
+        }
+    }
+
+    pub fn with_fallback(mut self, font: impl ToString) -> Self {
+        self.fonts.push(font.to_string());
+        self
+    }
+
+    /// First font that has all glyphs of text, or one that covers most of them.
+    pub fn select(&self, text: &str, draw: &DrawContext) -> Option<&str> {
+        let mut best = None;
+        let mut best_missing = usize::MAX;
+        for name in &self.fonts {
+            let Some(font) = draw.fonts.get(name.as_str()) else {
+                continue;
+            };
+            let missing = text
+                .chars()
+                .filter(|character| !character.is_whitespace() && !font.has_glyph(*character))
+                .count();
+            if missing == 0 {
+                return Some(name);
+            }
+            if missing < best_missing {
+                best = Some(name.as_str());
+                best_missing = missing;
+            }
+        }
+        best
+    }
+
+    /// Splits text into runs rendered by first font in chain having their glyphs.
+    pub fn runs<'a>(&'a self, text: &'a str, draw: &DrawContext) -> Vec<(&'a str, &'a str)> {
+        let mut result = Vec::<(&str, &str)>::new();
+        let mut start = 0;
+        let mut current = None;
+        for (index, character) in text.char_indices() {
+            let font = if character.is_whitespace() {
+                current
+            } else {
+                self.fonts
+                    .iter()
+                    .find(|name| {
+                        draw.fonts
+                            .get(name.as_str())
+                            .map(|font| font.has_glyph(character))
+                            .unwrap_or_default()
+                    })
+                    .or(self.fonts.first())
+                    .map(|name| name.as_str())
+            };
+            if font != current {
+                // Leading whitespace goes to the first run, so it starts at index 0.
+                if let Some(font) = current {
+                    result.push((font, &text[start..index]));
+                    start = index;
+                }
+                current = font;
+            }
+        }
+        if let Some(font) = current.or(self.fonts.first().map(|name| name.as_str())) {
+            if start < text.len() {
+                result.push((font, &text[start..]));
+            }
+        }
+        result
+    }
+}
+
+/// Text drawable rendering each run of text with font from fallback chain that has
+/// its glyphs. Font of wrapped text is ignored in favor of chain fonts.
+pub struct FallbackText {
+    pub text: Text,
+    pub chain: FontFallbackChain,
+}
+
+impl FallbackText {
+    pub fn new(text: Text, chain: FontFallbackChain) -> Self {
+        Self { text, chain }
+    }
+
+    pub fn layout(&self, draw: &DrawContext) -> Layout<Rgba<f32>> {
+        let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+        layout.reset(&LayoutSettings {
+            max_width: self.text.width,
+            max_height: self.text.height,
+            horizontal_align: self.text.horizontal_align,
+            vertical_align: self.text.vertical_align,
+            ..Default::default()
+        });
+        for (font, text) in self.chain.runs(&self.text.text, draw) {
+            if let Some(font_index) = draw.fonts.index_of(font) {
+                layout.append(
+                    draw.fonts.values(),
+                    &TextStyle {
+                        text,
+                        px: self.text.size,
+                        font_index,
+                        user_data: self.text.tint,
+                    },
+                );
+            }
+        }
+        layout
+    }
+}
+
+impl Drawable for FallbackText {
+    fn draw(&self, context: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
+        let layout = self.layout(context);
+        if layout.glyphs().is_empty() {
+            return;
+        }
+        context
+            .text_renderer
+            .include(context.fonts.values(), &layout);
+        graphics.stream.batch_optimized(GraphicsBatch {
+            shader: context.shader(self.text.shader.as_ref()),
+            uniforms: self
+                .text
+                .uniforms
+                .iter()
+                .map(|(k, v)| (k.clone(), v.to_owned()))
+                .chain(std::iter::once((
+                    "u_projection_view".into(),
+                    GlowUniformValue::M4(
+                        if self.text.screen_space {
+                            graphics.main_camera.screen_matrix()
+                        } else {
+                            graphics.main_camera.world_matrix()
+                        }
+                        .into_col_array(),
+                    ),
+                )))
+                .chain(std::iter::once(("u_image".into(), GlowUniformValue::I1(0))))
+                .collect(),
+            textures: if let Some(texture) = context.fonts_texture() {
+                vec![(texture, GlowTextureFiltering::Linear)]
+            } else {
+                vec![]
+            },
+            blending: self.text.blending.unwrap_or(GlowBlending::Alpha),
+            scissor: Default::default(),
+        });
+        let transform = context.top_transform() * transform_to_matrix(self.text.transform);
+        graphics.stream.transformed(
+            |stream| {
+                context.text_renderer.render_to_stream(stream);
+            },
+            |vertex| {
+                let point = transform.mul_point(Vec2::from(vertex.position));
+                vertex.position[0] = point.x;
+                vertex.position[1] = point.y;
+            },
+        );
+    }
+}
+
 pub struct FontAssetSubsystem;
 
 impl GameSubsystem for FontAssetSubsystem {
@@ -64,3 +245,64 @@ impl AssetProtocol for FontAssetProtocol {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_font_fallback_runs() {
+        let chain = FontFallbackChain::new("main").with_fallback("symbols");
+        let mut draw = DrawContext::default();
+        assert_eq!(
+            chain.runs("  hi there ", &draw),
+            vec![("main", "  hi there ")]
+        );
+        assert_eq!(chain.runs("   ", &draw), vec![("main", "   ")]);
+        assert!(chain.runs("", &draw).is_empty());
+
+        draw.fonts.insert(
+            "main",
+            Font::from_bytes(
+                include_bytes!("../../resources/roboto.ttf") as &[u8],
+                Default::default(),
+            )
+            .unwrap(),
+        );
+        draw.fonts.insert(
+            "symbols",
+            Font::from_bytes(
+                include_bytes!("../../resources/dejavu-sans-mono.ttf") as &[u8],
+                Default::default(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            chain.runs(" Жук ★★ and ←", &draw),
+            vec![
+                ("main", " Жук "),
+                ("symbols", "★★ "),
+                ("main", "and "),
+                ("symbols", "←"),
+            ]
+        );
+        assert_eq!(chain.select("Жук", &draw), Some("main"));
+        assert_eq!(chain.select("Жук ★", &draw), Some("symbols"));
+        assert_eq!(chain.select("你好", &draw), Some("main"));
+
+        let text = FallbackText::new(Text::default().text("a★"), chain);
+        let layout = text.layout(&draw);
+        let fonts = layout
+            .glyphs()
+            .iter()
+            .map(|glyph| glyph.font_index)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fonts,
+            vec![
+                draw.fonts.index_of("main").unwrap(),
+                draw.fonts.index_of("symbols").unwrap()
+            ]
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{context::GameContext, game::GameSubsystem, gamepad::GamepadMappings};
+use anput::world::World;
+use keket::{
+    database::{handle::AssetHandle, path::AssetPathStatic},
+    protocol::AssetProtocol,
+};
+use std::error::Error;
+
+pub struct GamepadMappingsAsset {
+    pub mappings: GamepadMappings,
+}
+
+/// Registers loaded mappings as defaults, below mappings from user config.
+/// Gamepad managers created with default constructor reload them on next maintenance.
+pub struct GamepadMappingsAssetSubsystem;
+
+impl GameSubsystem for GamepadMappingsAssetSubsystem {
+    fn run(&mut self, context: GameContext, _: f32) {
+        for entity in context
+            .assets
+            .storage
+            .added()
+            .iter_of::<GamepadMappingsAsset>()
+        {
+            if let Ok(asset) = context
+                .assets
+                .storage
+                .component::<true, GamepadMappingsAsset>(entity)
+            {
+                asset.mappings.register_default();
+            }
+        }
+    }
+}
+
+pub struct GamepadMappingsAssetProtocol;
+
+impl AssetProtocol for GamepadMappingsAssetProtocol {
+    fn name(&self) -> &str {
+        "gamepads"
+    }
+
+    fn process_bytes(
+        &mut self,
+        handle: AssetHandle,
+        storage: &mut World,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error>> {
+        let path = storage.component::<true, AssetPathStatic>(handle.entity())?;
+        let mappings = std::str::from_utf8(&bytes)
+            .map_err(|error| error.to_string())
+            .and_then(|content| GamepadMappings::parse(content).map_err(|error| error.to_string()))
+            .map_err(|error| {
+                format!(
+                    "Failed to load gamepad mappings: {:?}. {}",
+                    path.path(),
+                    error
+                )
+            })?;
+        drop(path);
+
+        storage.insert(handle.entity(), (GamepadMappingsAsset { mappings },))?;
+
+        Ok(())
+    }
+}
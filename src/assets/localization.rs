@@ -0,0 +1,91 @@
+use crate::{context::GameContext, game::GameSubsystem, localization::StringTable};
+use anput::{entity::Entity, world::World};
+use keket::{
+    database::{handle::AssetHandle, path::AssetPathStatic},
+    protocol::AssetProtocol,
+};
+use std::{collections::HashMap, error::Error};
+
+pub struct LocalizationAsset {
+    pub language: String,
+    pub table: StringTable,
+}
+
+#[derive(Default)]
+pub struct LocalizationAssetSubsystem {
+    tables: HashMap<Entity, (String, Vec<String>)>,
+}
+
+impl GameSubsystem for LocalizationAssetSubsystem {
+    fn run(&mut self, context: GameContext, _: f32) {
+        for entity in context
+            .assets
+            .storage
+            .added()
+            .iter_of::<LocalizationAsset>()
+        {
+            if let Ok(asset) = context
+                .assets
+                .storage
+                .component::<true, LocalizationAsset>(entity)
+            {
+                let keys = asset.table.entries.keys().cloned().collect();
+                context
+                    .localization
+                    .add_table(&asset.language, asset.table.clone());
+                self.tables.insert(entity, (asset.language.clone(), keys));
+            }
+        }
+        for entity in context
+            .assets
+            .storage
+            .removed()
+            .iter_of::<LocalizationAsset>()
+        {
+            if let Some((language, keys)) = self.tables.remove(&entity) {
+                if let Some(table) = context.localization.tables.get_mut(&language) {
+                    for key in keys {
+                        table.entries.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct LocalizationAssetProtocol;
+
+impl AssetProtocol for LocalizationAssetProtocol {
+    fn name(&self) -> &str {
+        "locale"
+    }
+
+    fn process_bytes(
+        &mut self,
+        handle: AssetHandle,
+        storage: &mut World,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error>> {
+        let path = storage.component::<true, AssetPathStatic>(handle.entity())?;
+        let language = path
+            .meta_items()
+            .find(|(key, _)| *key == "lang")
+            .map(|(_, value)| value)
+            .unwrap_or_else(|| {
+                let name = path.path().rsplit('/').next().unwrap_or_default();
+                name.split('.').next().unwrap_or(name)
+            })
+            .to_owned();
+        let table = std::str::from_utf8(&bytes)
+            .map_err(|error| error.to_string())
+            .and_then(|content| {
+                StringTable::load_from_str(content).map_err(|error| error.to_string())
+            })
+            .map_err(|error| format!("Failed to load locale: {:?}. {}", path.path(), error))?;
+        drop(path);
+
+        storage.insert(handle.entity(), (LocalizationAsset { language, table },))?;
+
+        Ok(())
+    }
+}
@@ -1,18 +1,35 @@
+pub mod bmfont;
 pub mod font;
+pub mod gamepad;
+pub mod localization;
 pub mod shader;
 pub mod sound;
 pub mod spine;
 pub mod texture;
+pub mod tiled;
 
 use crate::assets::{
-    font::FontAssetProtocol, shader::ShaderAssetProtocol, sound::SoundAssetProtocol,
-    spine::SpineAssetProtocol, texture::TextureAssetProtocol,
+    bmfont::BmFontAssetProtocol,
+    font::FontAssetProtocol,
+    gamepad::GamepadMappingsAssetProtocol,
+    localization::LocalizationAssetProtocol,
+    shader::{ShaderAssetProtocol, ShaderIncludeAssetProtocol},
+    sound::{MusicAssetProtocol, SfxAssetProtocol, SoundAssetProtocol, SoundGroupAssetProtocol},
+    spine::SpineAssetProtocol,
+    texture::TextureAssetProtocol,
+    tiled::TiledAssetProtocol,
 };
+use anput::{entity::Entity, world::World};
 use keket::{
-    database::{path::AssetPath, AssetDatabase},
+    database::{
+        events::{AssetEvent, AssetEventBindings, AssetEventKind},
+        handle::{AssetDependency, AssetHandle},
+        path::{AssetPath, AssetPathStatic},
+        AssetDatabase,
+    },
     fetch::{
         container::{ContainerAssetFetch, ContainerPartialFetch},
-        AssetFetch,
+        AssetAwaitsResolution, AssetFetch,
     },
     protocol::{bytes::BytesAssetProtocol, group::GroupAssetProtocol, text::TextAssetProtocol},
 };
@@ -25,6 +42,30 @@ use std::{
     path::Path,
 };
 
+/// Reports asset that failed after its bytes were processed (e.g. shader compilation),
+/// through database and asset event bindings, same as protocol processing failures.
+pub fn dispatch_processing_failure(
+    assets: &mut AssetDatabase,
+    entity: Entity,
+) -> Result<(), Box<dyn Error>> {
+    let event = AssetEvent {
+        handle: AssetHandle::new(entity),
+        kind: AssetEventKind::BytesProcessingFailed,
+        path: assets
+            .storage
+            .component::<true, AssetPathStatic>(entity)?
+            .clone(),
+    };
+    assets.events.dispatch(event.clone())?;
+    if let Ok(mut bindings) = assets
+        .storage
+        .component_mut::<true, AssetEventBindings>(entity)
+    {
+        bindings.dispatch(event)?;
+    }
+    Ok(())
+}
+
 pub fn name_from_path<'a>(path: &'a AssetPath<'a>) -> &'a str {
     path.meta_items()
         .find(|(key, _)| *key == "as")
@@ -32,16 +73,56 @@ pub fn name_from_path<'a>(path: &'a AssetPath<'a>) -> &'a str {
         .unwrap_or(path.path())
 }
 
+pub fn resolve_relative_path(base: &str, relative: &str) -> String {
+    let mut parts = base.split('/').collect::<Vec<_>>();
+    parts.pop();
+    for part in relative.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+pub fn ensure_dependency(
+    storage: &mut World,
+    handle: AssetHandle,
+    path: AssetPathStatic,
+) -> Result<Entity, Box<dyn Error>> {
+    let existing = storage
+        .query::<true, (Entity, &AssetPathStatic)>()
+        .find(|(_, item)| **item == path)
+        .map(|(entity, _)| entity);
+    let entity = match existing {
+        Some(entity) => entity,
+        None => storage.spawn((path, AssetAwaitsResolution))?,
+    };
+    storage.relate::<true, _>(AssetDependency, handle.entity(), entity)?;
+    Ok(entity)
+}
+
 pub fn make_database(fetch: impl AssetFetch) -> AssetDatabase {
     AssetDatabase::default()
         .with_protocol(BytesAssetProtocol)
         .with_protocol(TextAssetProtocol)
         .with_protocol(GroupAssetProtocol)
         .with_protocol(ShaderAssetProtocol)
+        .with_protocol(ShaderIncludeAssetProtocol)
         .with_protocol(TextureAssetProtocol)
         .with_protocol(FontAssetProtocol)
+        .with_protocol(BmFontAssetProtocol)
+        .with_protocol(LocalizationAssetProtocol)
+        .with_protocol(GamepadMappingsAssetProtocol)
         .with_protocol(SoundAssetProtocol)
+        .with_protocol(MusicAssetProtocol)
+        .with_protocol(SoundGroupAssetProtocol)
+        .with_protocol(SfxAssetProtocol)
         .with_protocol(SpineAssetProtocol)
+        .with_protocol(TiledAssetProtocol)
         .with_fetch(fetch)
 }
 
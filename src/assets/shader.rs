@@ -1,56 +1,373 @@
-use crate::{assets::name_from_path, context::GameContext, game::GameSubsystem};
-use anput::world::World;
+use crate::{
+    assets::{
+        dispatch_processing_failure, ensure_dependency, name_from_path, resolve_relative_path,
+    },
+    context::GameContext,
+    game::GameSubsystem,
+};
+use anput::{entity::Entity, world::World};
 use keket::{
-    database::{handle::AssetHandle, path::AssetPathStatic},
+    database::{
+        handle::{AssetDependency, AssetHandle},
+        path::{AssetPath, AssetPathStatic},
+    },
     protocol::AssetProtocol,
 };
-use std::{borrow::Cow, error::Error};
+use std::{borrow::Cow, collections::HashMap, error::Error};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderSourceLine {
+    pub file: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ShaderSourceMap {
+    pub vertex: Vec<ShaderSourceLine>,
+    pub fragment: Vec<ShaderSourceLine>,
+}
 
 pub struct ShaderAsset {
     pub vertex: Cow<'static, str>,
     pub fragment: Cow<'static, str>,
+    pub source_map: Option<ShaderSourceMap>,
 }
 
 impl ShaderAsset {
+    pub const ERROR_VERTEX: &'static str = r#"#version 300 es
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec3 a_uv;
+layout(location = 2) in vec4 a_color;
+out vec3 v_uv;
+uniform mat4 u_projection_view;
+
+void main() {
+    gl_Position = u_projection_view * vec4(a_position, 0.0, 1.0);
+    v_uv = a_uv;
+}
+"#;
+
+    pub const ERROR_FRAGMENT: &'static str = r#"#version 300 es
+precision highp float;
+in vec3 v_uv;
+out vec4 o_color;
+
+void main() {
+    vec2 cell = floor(v_uv.xy * 8.0);
+    float checker = mod(cell.x + cell.y, 2.0);
+    o_color = mix(vec4(1.0, 0.0, 1.0, 1.0), vec4(0.0, 0.0, 0.0, 1.0), checker);
+}
+"#;
+
     pub fn new(vertex: &'static str, fragment: &'static str) -> Self {
         Self {
             vertex: vertex.into(),
             fragment: fragment.into(),
+            source_map: None,
+        }
+    }
+
+    pub fn error() -> Self {
+        Self::new(Self::ERROR_VERTEX, Self::ERROR_FRAGMENT)
+    }
+
+    pub fn preprocess(
+        path: &str,
+        source: &str,
+        defines: &[(String, String)],
+        includes: &HashMap<String, String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        fn expand(
+            path: &str,
+            source: &str,
+            includes: &HashMap<String, String>,
+            stack: &mut Vec<String>,
+            result: &mut Vec<(String, ShaderSourceLine)>,
+        ) -> Result<(), Box<dyn Error>> {
+            if stack.iter().any(|item| item == path) {
+                return Err(
+                    format!("Shader include cycle: {} -> {}", stack.join(" -> "), path).into(),
+                );
+            }
+            stack.push(path.to_owned());
+            for (index, line) in source.lines().enumerate() {
+                if let Some(include) = parse_include(line) {
+                    let include = resolve_relative_path(path, include);
+                    let source = includes.get(&include).ok_or_else(|| {
+                        format!(
+                            "Shader include: `{}` not found in: `{}:{}`",
+                            include,
+                            path,
+                            index + 1
+                        )
+                    })?;
+                    expand(&include, source, includes, stack, result)?;
+                } else {
+                    result.push((
+                        line.to_owned(),
+                        ShaderSourceLine {
+                            file: path.to_owned(),
+                            line: index + 1,
+                        },
+                    ));
+                }
+            }
+            stack.pop();
+            Ok(())
+        }
+
+        fn finalize(
+            mut lines: Vec<(String, ShaderSourceLine)>,
+            defines: &[(String, String)],
+        ) -> (String, Vec<ShaderSourceLine>) {
+            while lines
+                .first()
+                .map(|(line, _)| line.trim().is_empty())
+                .unwrap_or_default()
+            {
+                lines.remove(0);
+            }
+            while lines
+                .last()
+                .map(|(line, _)| line.trim().is_empty())
+                .unwrap_or_default()
+            {
+                lines.pop();
+            }
+            let defines = defines.iter().map(|(name, value)| {
+                (
+                    format!("#define {} {}", name, value),
+                    ShaderSourceLine {
+                        file: format!("?define={}", name),
+                        line: 0,
+                    },
+                )
+            });
+            let index = lines
+                .iter()
+                .position(|(line, _)| line.trim().starts_with("#version"))
+                .map(|index| index + 1)
+                .unwrap_or_default();
+            lines.splice(index..index, defines);
+            let mut code = String::default();
+            let mut map = Vec::with_capacity(lines.len());
+            for (line, source) in lines {
+                code.push_str(&line);
+                code.push('\n');
+                map.push(source);
+            }
+            (code, map)
+        }
+
+        enum Mode {
+            Vertex,
+            Fragment,
+        }
+
+        let mut expanded = vec![];
+        expand(path, source, includes, &mut vec![], &mut expanded)?;
+
+        let mut vertex = vec![];
+        let mut fragment = vec![];
+        let mut mode = Mode::Vertex;
+        for (line, source) in expanded {
+            let trimmed = line.trim();
+            if let Some(comment) = trimmed.strip_prefix("///") {
+                let comment = comment.trim().to_lowercase();
+                if comment == "[vertex]" {
+                    mode = Mode::Vertex;
+                    continue;
+                }
+                if comment == "[fragment]" {
+                    mode = Mode::Fragment;
+                    continue;
+                }
+            }
+            match mode {
+                Mode::Vertex => vertex.push((line, source)),
+                Mode::Fragment => fragment.push((line, source)),
+            }
+        }
+
+        let (vertex, vertex_map) = finalize(vertex, defines);
+        let (fragment, fragment_map) = finalize(fragment, defines);
+        Ok(Self {
+            vertex: vertex.into(),
+            fragment: fragment.into(),
+            source_map: Some(ShaderSourceMap {
+                vertex: vertex_map,
+                fragment: fragment_map,
+            }),
+        })
+    }
+
+    pub fn annotate_error(&self, error: &str) -> String {
+        let lowercase = error.to_lowercase();
+        let vertex = !lowercase.contains("fragment");
+        let fragment = !lowercase.contains("vertex");
+        let mut result = String::default();
+        for message in error.lines() {
+            result.push_str(message);
+            result.push('\n');
+            let Some(line) = parse_error_line(message) else {
+                continue;
+            };
+            let stages = [
+                (
+                    vertex,
+                    "vertex",
+                    self.vertex.trim(),
+                    self.source_map.as_ref().map(|map| map.vertex.as_slice()),
+                ),
+                (
+                    fragment,
+                    "fragment",
+                    self.fragment.trim(),
+                    self.source_map.as_ref().map(|map| map.fragment.as_slice()),
+                ),
+            ];
+            for (enabled, stage, code, map) in stages {
+                if !enabled {
+                    continue;
+                }
+                let Some(code) = code.lines().nth(line.saturating_sub(1)) else {
+                    continue;
+                };
+                match map.and_then(|map| map.get(line.saturating_sub(1))) {
+                    Some(source) => result.push_str(&format!(
+                        "    --> {} {}:{} | {}\n",
+                        stage,
+                        source.file,
+                        source.line,
+                        code.trim()
+                    )),
+                    None => result.push_str(&format!(
+                        "    --> {} line {} | {}\n",
+                        stage,
+                        line,
+                        code.trim()
+                    )),
+                }
+            }
         }
+        result
     }
 }
 
+pub struct ShaderSourceAsset {
+    pub source: String,
+    pub defines: Vec<(String, String)>,
+}
+
+pub struct ShaderIncludeAsset {
+    pub source: String,
+}
+
 pub struct ShaderAssetSubsystem;
 
 impl GameSubsystem for ShaderAssetSubsystem {
     fn run(&mut self, context: GameContext, _: f32) {
+        let mut failed = Self::resolve_sources(&mut context.assets.storage);
+
         for entity in context.assets.storage.added().iter_of::<ShaderAsset>() {
             if let Some((path, asset)) = context
                 .assets
                 .storage
                 .lookup_one::<true, (&AssetPathStatic, &ShaderAsset)>(entity)
             {
-                context.draw.shaders.insert(
-                    name_from_path(&path).to_owned().into(),
-                    context
-                        .graphics
-                        .shader(asset.vertex.trim(), asset.fragment.trim())
-                        .unwrap(),
-                );
+                let shader = match context
+                    .graphics
+                    .shader(asset.vertex.trim(), asset.fragment.trim())
+                {
+                    Ok(shader) => shader,
+                    Err(error) => {
+                        let error = format!(
+                            "Shader: `{}` failed to compile:\n{}",
+                            path.path(),
+                            asset.annotate_error(&error.to_string())
+                        );
+                        spitfire_glow::console_log!("* {}", error);
+                        failed.push(entity);
+                        match context
+                            .graphics
+                            .shader(ShaderAsset::ERROR_VERTEX, ShaderAsset::ERROR_FRAGMENT)
+                        {
+                            Ok(shader) => shader,
+                            Err(_) => continue,
+                        }
+                    }
+                };
+                context
+                    .draw
+                    .shaders
+                    .insert(shader_name(&path).into(), shader);
             }
         }
+        for entity in failed {
+            let _ = dispatch_processing_failure(context.assets, entity);
+        }
         for entity in context.assets.storage.removed().iter_of::<ShaderAsset>() {
             if let Some(path) = context
                 .assets
                 .storage
                 .lookup_one::<true, &AssetPathStatic>(entity)
             {
-                context.draw.shaders.remove(name_from_path(&path));
+                context.draw.shaders.remove(shader_name(&path).as_str());
             }
         }
     }
 }
 
+impl ShaderAssetSubsystem {
+    /// Preprocesses sources with all includes loaded, returns ones that failed.
+    fn resolve_sources(storage: &mut World) -> Vec<Entity> {
+        let pending = storage
+            .query::<true, (Entity, &ShaderSourceAsset)>()
+            .filter(|(entity, _)| !storage.has_entity_component::<ShaderAsset>(*entity))
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        let mut failed = vec![];
+        if pending.is_empty() {
+            return failed;
+        }
+        let includes = storage
+            .query::<true, (&AssetPathStatic, &ShaderIncludeAsset)>()
+            .map(|(path, asset)| (path.path().to_owned(), asset.source.to_owned()))
+            .collect::<HashMap<_, _>>();
+        for entity in pending {
+            let awaiting = storage
+                .traverse_outgoing::<true, AssetDependency>([entity])
+                .any(|(_, dependency)| {
+                    dependency != entity
+                        && !storage.has_entity_component::<ShaderIncludeAsset>(dependency)
+                });
+            if awaiting {
+                continue;
+            }
+            let Some(result) = storage
+                .lookup_one::<true, (&AssetPathStatic, &ShaderSourceAsset)>(entity)
+                .map(|(path, asset)| {
+                    ShaderAsset::preprocess(path.path(), &asset.source, &asset.defines, &includes)
+                        .map_err(|error| format!("Shader: `{}` {}", path.path(), error))
+                })
+            else {
+                continue;
+            };
+            match result {
+                Ok(asset) => {
+                    let _ = storage.insert(entity, (asset,));
+                }
+                Err(error) => {
+                    spitfire_glow::console_log!("* {}", error);
+                    let _ = storage.insert(entity, (ShaderAsset::error(),));
+                    failed.push(entity);
+                }
+            }
+        }
+        failed
+    }
+}
+
 pub struct ShaderAssetProtocol;
 
 impl AssetProtocol for ShaderAssetProtocol {
@@ -64,47 +381,181 @@ impl AssetProtocol for ShaderAssetProtocol {
         storage: &mut World,
         bytes: Vec<u8>,
     ) -> Result<(), Box<dyn Error>> {
-        enum Mode {
-            Vertex,
-            Fragment,
+        let path = storage.component::<true, AssetPathStatic>(handle.entity())?;
+        let base = path.path().to_owned();
+        let defines = path
+            .meta_items()
+            .filter(|(key, _)| *key == "define")
+            .flat_map(|(_, value)| value.split(','))
+            .filter(|value| !value.is_empty())
+            .map(|value| match value.split_once(':') {
+                Some((name, value)) => (name.to_owned(), value.to_owned()),
+                None => (value.to_owned(), "1".to_owned()),
+            })
+            .collect();
+        drop(path);
+        let source = String::from_utf8(bytes)?;
+
+        for include in scan_includes(&base, &source) {
+            ensure_dependency(storage, handle, include)?;
         }
+        storage.insert(handle.entity(), (ShaderSourceAsset { source, defines },))?;
 
-        let mut vertex = String::default();
-        let mut fragment = String::default();
-        let mut mode = Mode::Vertex;
-        for line in std::str::from_utf8(&bytes)?.lines() {
-            let trimmed = line.trim();
-            if let Some(comment) = trimmed.strip_prefix("///") {
-                let comment = comment.trim().to_lowercase();
-                if comment == "[vertex]" {
-                    mode = Mode::Vertex;
-                    continue;
-                }
-                if comment == "[fragment]" {
-                    mode = Mode::Fragment;
-                    continue;
-                }
-            }
-            match mode {
-                Mode::Vertex => {
-                    vertex.push_str(line);
-                    vertex.push('\n');
-                }
-                Mode::Fragment => {
-                    fragment.push_str(line);
-                    fragment.push('\n');
-                }
+        Ok(())
+    }
+}
+
+pub struct ShaderIncludeAssetProtocol;
+
+impl AssetProtocol for ShaderIncludeAssetProtocol {
+    fn name(&self) -> &str {
+        "shader-include"
+    }
+
+    fn process_bytes(
+        &mut self,
+        handle: AssetHandle,
+        storage: &mut World,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error>> {
+        let base = storage
+            .component::<true, AssetPathStatic>(handle.entity())?
+            .path()
+            .to_owned();
+        let source = String::from_utf8(bytes)?;
+
+        for include in scan_includes(&base, &source) {
+            ensure_dependency(storage, handle, include)?;
+        }
+        storage.insert(handle.entity(), (ShaderIncludeAsset { source },))?;
+
+        Ok(())
+    }
+}
+
+/// Shader name of path, with defines folded in so each variant gets own name,
+/// unless explicit `as` name was given.
+fn shader_name(path: &AssetPath) -> String {
+    if path.has_meta_key("as") || !path.has_meta_key("define") {
+        return name_from_path(path).to_owned();
+    }
+    let defines = path
+        .meta_items()
+        .filter(|(key, _)| *key == "define")
+        .map(|(_, value)| value)
+        .collect::<Vec<_>>();
+    format!("{}?define={}", path.path(), defines.join(","))
+}
+
+fn parse_include(line: &str) -> Option<&str> {
+    let path = line.trim().strip_prefix("#include")?.trim();
+    path.strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+        .or_else(|| {
+            path.strip_prefix('<')
+                .and_then(|path| path.strip_suffix('>'))
+        })
+}
+
+fn parse_error_line(message: &str) -> Option<usize> {
+    for (prefix, suffix) in [("0:", ':'), ("0(", ')')] {
+        let mut rest = message;
+        while let Some(index) = rest.find(prefix) {
+            rest = &rest[(index + prefix.len())..];
+            let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            if digits > 0 && rest[digits..].starts_with(suffix) {
+                return rest[..digits].parse().ok();
             }
         }
+    }
+    None
+}
 
-        storage.insert(
-            handle.entity(),
-            (ShaderAsset {
-                vertex: vertex.into(),
-                fragment: fragment.into(),
-            },),
-        )?;
+fn scan_includes(base: &str, source: &str) -> Vec<AssetPathStatic> {
+    source
+        .lines()
+        .filter_map(parse_include)
+        .map(|include| {
+            AssetPathStatic::new(format!(
+                "shader-include://{}",
+                resolve_relative_path(base, include)
+            ))
+        })
+        .collect()
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::{shader_name, ShaderAsset, ShaderSourceLine};
+    use keket::database::path::AssetPath;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_shader_preprocess() {
+        let includes = HashMap::from([
+            (
+                "shaders/include/vertex.glsl".to_owned(),
+                "void main() {}".to_owned(),
+            ),
+            (
+                "shaders/include/precision.glsl".to_owned(),
+                "#include \"common.glsl\"\nprecision highp float;".to_owned(),
+            ),
+            (
+                "shaders/include/common.glsl".to_owned(),
+                "// common".to_owned(),
+            ),
+        ]);
+        let source = "/// [vertex]\n#version 300 es\n#include \"include/vertex.glsl\"\n\n/// [fragment]\n#version 300 es\n#include <include/precision.glsl>\n#ifdef LIT\n#endif";
+        let asset = ShaderAsset::preprocess(
+            "shaders/main.glsl",
+            source,
+            &[
+                ("LIT".to_owned(), "1".to_owned()),
+                ("LIGHTS".to_owned(), "4".to_owned()),
+            ],
+            &includes,
+        )
+        .unwrap();
+        assert_eq!(
+            asset.vertex,
+            "#version 300 es\n#define LIT 1\n#define LIGHTS 4\nvoid main() {}\n"
+        );
+        assert_eq!(
+            asset.fragment,
+            "#version 300 es\n#define LIT 1\n#define LIGHTS 4\n// common\nprecision highp float;\n#ifdef LIT\n#endif\n"
+        );
+        assert_eq!(
+            asset.source_map.as_ref().unwrap().fragment[4],
+            ShaderSourceLine {
+                file: "shaders/include/precision.glsl".to_owned(),
+                line: 2,
+            }
+        );
+        assert_eq!(
+            asset.annotate_error("ERROR: 0:5: 'float' : syntax error"),
+            "ERROR: 0:5: 'float' : syntax error\n    --> fragment shaders/include/precision.glsl:2 | precision highp float;\n"
+        );
+
+        let includes = HashMap::from([
+            ("a.glsl".to_owned(), "#include \"b.glsl\"".to_owned()),
+            ("b.glsl".to_owned(), "#include \"a.glsl\"".to_owned()),
+        ]);
+        assert!(ShaderAsset::preprocess("a.glsl", "#include \"b.glsl\"", &[], &includes).is_err());
+
+        assert_eq!(
+            shader_name(&AssetPath::new(
+                "shader://lit.glsl?define=LIT&define=LIGHTS:4"
+            )),
+            "lit.glsl?define=LIT,LIGHTS:4"
+        );
+        assert_eq!(
+            shader_name(&AssetPath::new("shader://lit.glsl?define=LIT&as=lit")),
+            "lit"
+        );
+        assert_eq!(
+            shader_name(&AssetPath::new("shader://lit.glsl")),
+            "lit.glsl"
+        );
     }
 }
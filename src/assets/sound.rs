@@ -1,17 +1,33 @@
-use crate::{assets::name_from_path, context::GameContext, game::GameSubsystem};
-use anput::world::World;
+use crate::{
+    assets::name_from_path,
+    audio::{group::SoundGroupManifest, sfx::SfxDescription},
+    context::GameContext,
+    game::GameSubsystem,
+};
+use anput::{entity::Entity, world::World};
 use keket::{
     database::{handle::AssetHandle, path::AssetPathStatic},
     protocol::AssetProtocol,
 };
-use kira::sound::static_sound::StaticSoundData;
-use std::{error::Error, io::Cursor};
+use kira::sound::{static_sound::StaticSoundData, streaming::StreamingSoundData};
+use std::{collections::HashMap, error::Error, io::Cursor, sync::Arc};
 
 pub struct SoundAsset {
     pub data: StaticSoundData,
 }
 
-pub struct SoundAssetSubsystem;
+pub struct MusicAsset {
+    pub bytes: Arc<[u8]>,
+}
+
+pub struct SoundGroupAsset {
+    pub manifest: SoundGroupManifest,
+}
+
+#[derive(Default)]
+pub struct SoundAssetSubsystem {
+    groups: HashMap<Entity, Vec<String>>,
+}
 
 impl GameSubsystem for SoundAssetSubsystem {
     fn run(&mut self, context: GameContext, _: f32) {
@@ -36,6 +52,50 @@ impl GameSubsystem for SoundAssetSubsystem {
                 context.audio.sounds.remove(name_from_path(&path));
             }
         }
+        for entity in context.assets.storage.added().iter_of::<MusicAsset>() {
+            if let Some((path, asset)) = context
+                .assets
+                .storage
+                .lookup_one::<true, (&AssetPathStatic, &MusicAsset)>(entity)
+            {
+                context
+                    .audio
+                    .streams
+                    .insert(name_from_path(&path).to_owned(), asset.bytes.clone());
+            }
+        }
+        for entity in context.assets.storage.removed().iter_of::<MusicAsset>() {
+            if let Some(path) = context
+                .assets
+                .storage
+                .lookup_one::<true, &AssetPathStatic>(entity)
+            {
+                context.audio.streams.remove(name_from_path(&path));
+            }
+        }
+        for entity in context.assets.storage.added().iter_of::<SoundGroupAsset>() {
+            if let Ok(asset) = context
+                .assets
+                .storage
+                .component::<true, SoundGroupAsset>(entity)
+            {
+                let names = self.groups.entry(entity).or_default();
+                for (name, group) in &asset.manifest.groups {
+                    context.audio.groups.insert(name.to_owned(), group.clone());
+                    names.push(name.to_owned());
+                }
+            }
+        }
+        for entity in context
+            .assets
+            .storage
+            .removed()
+            .iter_of::<SoundGroupAsset>()
+        {
+            for name in self.groups.remove(&entity).unwrap_or_default() {
+                context.audio.groups.remove(&name);
+            }
+        }
     }
 }
 
@@ -53,6 +113,10 @@ impl AssetProtocol for SoundAssetProtocol {
         bytes: Vec<u8>,
     ) -> Result<(), Box<dyn Error>> {
         let path = storage.component::<true, AssetPathStatic>(handle.entity())?;
+        if path.meta_items().any(|(key, _)| key == "stream") {
+            drop(path);
+            return MusicAssetProtocol.process_bytes(handle, storage, bytes);
+        }
         let data = StaticSoundData::from_cursor(Cursor::new(bytes))
             .map_err(|_| format!("Failed to load sound: {:?}", path.path()))?;
         drop(path);
@@ -62,3 +126,89 @@ impl AssetProtocol for SoundAssetProtocol {
         Ok(())
     }
 }
+
+pub struct SfxAssetProtocol;
+
+impl AssetProtocol for SfxAssetProtocol {
+    fn name(&self) -> &str {
+        "sfx"
+    }
+
+    fn process_bytes(
+        &mut self,
+        handle: AssetHandle,
+        storage: &mut World,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error>> {
+        let path = storage.component::<true, AssetPathStatic>(handle.entity())?;
+        let description = std::str::from_utf8(&bytes)
+            .map_err(|error| error.to_string())
+            .and_then(|content| {
+                SfxDescription::load_from_str(content).map_err(|error| error.to_string())
+            })
+            .map_err(|error| format!("Failed to load sfx: {:?}. {}", path.path(), error))?;
+        let data = description
+            .sound_data()
+            .map_err(|_| format!("Failed to synthesize sfx: {:?}", path.path()))?;
+        drop(path);
+
+        storage.insert(handle.entity(), (SoundAsset { data },))?;
+
+        Ok(())
+    }
+}
+
+pub struct MusicAssetProtocol;
+
+impl AssetProtocol for MusicAssetProtocol {
+    fn name(&self) -> &str {
+        "music"
+    }
+
+    fn process_bytes(
+        &mut self,
+        handle: AssetHandle,
+        storage: &mut World,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error>> {
+        let path = storage.component::<true, AssetPathStatic>(handle.entity())?;
+        let bytes = Arc::<[u8]>::from(bytes);
+        StreamingSoundData::from_cursor(Cursor::new(bytes.clone()))
+            .map_err(|_| format!("Failed to load music: {:?}", path.path()))?;
+        drop(path);
+
+        storage.insert(handle.entity(), (MusicAsset { bytes },))?;
+
+        Ok(())
+    }
+}
+
+pub struct SoundGroupAssetProtocol;
+
+impl AssetProtocol for SoundGroupAssetProtocol {
+    fn name(&self) -> &str {
+        "sound-group"
+    }
+
+    fn process_bytes(
+        &mut self,
+        handle: AssetHandle,
+        storage: &mut World,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error>> {
+        let path = storage.component::<true, AssetPathStatic>(handle.entity())?;
+        let manifest = std::str::from_utf8(&bytes)
+            .map_err(|error| error.to_string())
+            .and_then(|content| {
+                SoundGroupManifest::load_from_str(content).map_err(|error| error.to_string())
+            })
+            .map_err(|error| {
+                format!("Failed to load sound groups: {:?}. {}", path.path(), error)
+            })?;
+        drop(path);
+
+        storage.insert(handle.entity(), (SoundGroupAsset { manifest },))?;
+
+        Ok(())
+    }
+}
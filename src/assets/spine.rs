@@ -77,11 +77,7 @@ impl AssetProtocol for SpineAssetProtocol {
             archive.by_name(&atlas_page_name)?.read_to_end(&mut bytes)?;
             let image = image::load_from_memory(&bytes)?.into_rgba8();
             let path = AssetPathStatic::new(format!("texture://{path_part}/{atlas_page_name}"));
-            let asset = TextureAsset {
-                image,
-                cols: 1,
-                rows: 1,
-            };
+            let asset = TextureAsset::new(image);
             let entity = storage.spawn((path.clone(), asset))?;
             textures.insert(atlas_page_name, path);
             storage.relate::<true, _>(AssetDependency, handle.entity(), entity)?;
@@ -1,17 +1,117 @@
 use crate::{assets::name_from_path, context::GameContext, game::GameSubsystem};
 use anput::world::World;
-use image::{GenericImage, GenericImageView, RgbaImage};
+use image::{imageops, GenericImage, GenericImageView, Rgba, RgbaImage};
 use keket::{
-    database::{handle::AssetHandle, path::AssetPathStatic},
+    database::{handle::AssetHandle, path::AssetPathStatic, AssetDatabase},
     protocol::AssetProtocol,
 };
-use spitfire_glow::renderer::GlowTextureFormat;
-use std::error::Error;
+use spitfire_draw::{
+    sprite::{Sprite, SpriteTexture},
+    utils::TextureRef,
+};
+use spitfire_glow::renderer::{GlowTextureFiltering, GlowTextureFormat};
+use std::{borrow::Cow, error::Error};
+use vek::{Rect, Vec2};
 
 pub struct TextureAsset {
     pub image: RgbaImage,
     pub cols: u32,
     pub rows: u32,
+    pub format: GlowTextureFormat,
+    /// Requested with `filter` option, used by sprite textures made from this asset.
+    pub filtering: GlowTextureFiltering,
+    /// Border added around each page with `pad` option, excluded from page regions.
+    pub padding: u32,
+}
+
+impl TextureAsset {
+    pub fn new(image: RgbaImage) -> Self {
+        Self {
+            image,
+            cols: 1,
+            rows: 1,
+            format: GlowTextureFormat::Rgba,
+            filtering: GlowTextureFiltering::Linear,
+            padding: 0,
+        }
+    }
+
+    pub fn pages(&self) -> u32 {
+        self.cols * self.rows
+    }
+
+    pub fn sprite_texture(
+        &self,
+        sampler: impl Into<Cow<'static, str>>,
+        name: impl Into<Cow<'static, str>>,
+    ) -> SpriteTexture {
+        SpriteTexture {
+            sampler: sampler.into(),
+            texture: TextureRef::name(name),
+            filtering: self.filtering,
+        }
+    }
+
+    /// Maps normalized region of original page into padded page.
+    pub fn inset_region(&self, region: Rect<f32, f32>) -> Rect<f32, f32> {
+        let padded = Vec2::new(
+            self.image.width().max(1) as f32,
+            (self.image.height() / self.pages()).max(1) as f32,
+        );
+        let padding = self.padding as f32;
+        let size = padded - padding * 2.0;
+        Rect::new(
+            (padding + region.x * size.x) / padded.x,
+            (padding + region.y * size.y) / padded.y,
+            region.w * size.x / padded.x,
+            region.h * size.y / padded.y,
+        )
+    }
+
+    pub fn region(&self) -> Rect<f32, f32> {
+        self.inset_region(Rect::new(0.0, 0.0, 1.0, 1.0))
+    }
+
+    pub fn sprite(
+        &self,
+        sampler: impl Into<Cow<'static, str>>,
+        name: impl Into<Cow<'static, str>>,
+    ) -> Sprite {
+        Sprite::single(self.sprite_texture(sampler, name)).region_page(self.region(), 0.0)
+    }
+
+    fn texture_data(&self) -> Cow<'_, [u8]> {
+        match self.format {
+            GlowTextureFormat::Monochromatic => {
+                Cow::Owned(imageops::grayscale(&self.image).into_raw())
+            }
+            _ => Cow::Borrowed(self.image.as_raw()),
+        }
+    }
+}
+
+/// Sprite of loaded texture with given name, filtered as requested by its `filter`
+/// option and skipping its `pad` border. Uses linear filtering and whole texture
+/// when texture is not loaded.
+pub fn sprite(
+    assets: &AssetDatabase,
+    sampler: impl Into<Cow<'static, str>>,
+    name: impl Into<Cow<'static, str>>,
+) -> Sprite {
+    let sampler = sampler.into();
+    let name = name.into();
+    assets
+        .storage
+        .query::<true, (&AssetPathStatic, &TextureAsset)>()
+        .find(|(path, _)| name_from_path(path) == name)
+        .map(|(_, asset)| asset.sprite(sampler.clone(), name.clone()))
+        .unwrap_or_else(|| {
+            Sprite::single(SpriteTexture {
+                sampler,
+                texture: TextureRef::name(name),
+                filtering: GlowTextureFiltering::Linear,
+            })
+        })
 }
 
 pub struct TextureAssetSubsystem;
@@ -24,7 +124,8 @@ impl GameSubsystem for TextureAssetSubsystem {
                 .storage
                 .lookup_one::<true, (&AssetPathStatic, &TextureAsset)>(entity)
             {
-                let pages = asset.cols * asset.rows;
+                let pages = asset.pages();
+                let data = asset.texture_data();
                 context.draw.textures.insert(
                     name_from_path(&path).to_owned().into(),
                     context
@@ -33,8 +134,8 @@ impl GameSubsystem for TextureAssetSubsystem {
                             asset.image.width(),
                             asset.image.height() / pages,
                             pages,
-                            GlowTextureFormat::Rgba,
-                            Some(asset.image.as_raw()),
+                            asset.format,
+                            Some(&data),
                         )
                         .unwrap(),
                 );
@@ -68,28 +169,119 @@ impl AssetProtocol for TextureAssetProtocol {
         let path = storage.component::<true, AssetPathStatic>(handle.entity())?;
         let mut cols = 1;
         let mut rows = 1;
+        let mut format = GlowTextureFormat::Rgba;
+        let mut filtering = GlowTextureFiltering::Linear;
+        let mut premultiply = false;
+        let mut color_key = None;
+        let mut padding = 0;
         for (key, value) in path.meta_items() {
-            if key == "cols" || key == "c" {
-                cols = value.parse().unwrap_or(1);
-            } else if key == "rows" || key == "r" {
-                rows = value.parse().unwrap_or(1);
+            let enabled = value != "false" && value != "0";
+            if key == "cols" || key == "c" || key == "rows" || key == "r" {
+                let count = value
+                    .parse()
+                    .ok()
+                    .filter(|count| *count > 0)
+                    .ok_or_else(|| {
+                        format!(
+                            "Invalid texture `{}` count: `{}` in: {:?}",
+                            key,
+                            value,
+                            path.path()
+                        )
+                    })?;
+                if key.starts_with('c') {
+                    cols = count;
+                } else {
+                    rows = count;
+                }
+            } else if key == "filter" {
+                filtering = match value {
+                    "nearest" => GlowTextureFiltering::Nearest,
+                    "linear" => GlowTextureFiltering::Linear,
+                    _ => {
+                        return Err(format!(
+                            "Unknown texture filter: `{}` in: {:?}",
+                            value,
+                            path.path()
+                        )
+                        .into())
+                    }
+                };
+            } else if key == "premultiply" {
+                premultiply = enabled;
+            } else if key == "colorkey" {
+                color_key = Some(parse_color(value).ok_or_else(|| {
+                    format!(
+                        "Invalid texture color key: `{}` in: {:?}",
+                        value,
+                        path.path()
+                    )
+                })?);
+            } else if key == "format" {
+                format = match value {
+                    "rgba" => GlowTextureFormat::Rgba,
+                    "mono" | "monochromatic" => GlowTextureFormat::Monochromatic,
+                    _ => {
+                        return Err(format!(
+                            "Unknown texture format: `{}` in: {:?}",
+                            value,
+                            path.path()
+                        )
+                        .into())
+                    }
+                };
+            } else if key == "pad" {
+                padding = value.parse().map_err(|_| {
+                    format!("Invalid texture padding: `{}` in: {:?}", value, path.path())
+                })?;
+            } else if key == "mipmaps" && enabled {
+                return Err(format!(
+                    "Texture mipmaps are not supported, renderer samples only base level: {:?}",
+                    path.path()
+                )
+                .into());
             }
         }
         let mut image = image::load_from_memory(&bytes)
             .map_err(|_| format!("Failed to load texture: {:?}", path.path()))?
             .into_rgba8();
         drop(path);
+        if let Some(color_key) = color_key {
+            for pixel in image.pixels_mut() {
+                if pixel.0[0..3] == color_key {
+                    *pixel = Rgba([0, 0, 0, 0]);
+                }
+            }
+        }
+        if premultiply {
+            for pixel in image.pixels_mut() {
+                let alpha = pixel.0[3] as u32;
+                for channel in &mut pixel.0[0..3] {
+                    *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+                }
+            }
+        }
         let pages = cols * rows;
-        image = if cols > 1 || rows > 1 {
+        image = if cols > 1 || rows > 1 || padding > 0 {
             let width = image.width() / cols;
             let height = image.height() / rows;
-            let mut result = RgbaImage::new(width, height * pages);
+            let padded_width = width + padding * 2;
+            let padded_height = height + padding * 2;
+            let mut result = RgbaImage::new(padded_width, padded_height * pages);
             for row in 0..rows {
                 for col in 0..cols {
                     let view = image.view(col * width, row * height, width, height);
-                    result
-                        .copy_from(&*view, 0, (row * cols + col) * height)
-                        .unwrap();
+                    let offset = (row * cols + col) * padded_height;
+                    result.copy_from(&*view, padding, offset + padding).unwrap();
+                    for y in 0..padded_height {
+                        for x in 0..padded_width {
+                            let source_x = x.clamp(padding, padding + width - 1) - padding;
+                            let source_y = y.clamp(padding, padding + height - 1) - padding;
+                            if source_x + padding != x || source_y + padding != y {
+                                result.put_pixel(x, offset + y, view.get_pixel(source_x, source_y));
+                            }
+                        }
+                    }
                 }
             }
             result
@@ -97,8 +289,71 @@ impl AssetProtocol for TextureAssetProtocol {
             image
         };
 
-        storage.insert(handle.entity(), (TextureAsset { image, cols, rows },))?;
+        storage.insert(
+            handle.entity(),
+            (TextureAsset {
+                image,
+                cols,
+                rows,
+                format,
+                filtering,
+                padding,
+            },),
+        )?;
 
         Ok(())
     }
 }
+
+fn parse_color(value: &str) -> Option<[u8; 3]> {
+    let value = value.trim_start_matches('#');
+    if value.len() != 6 {
+        return None;
+    }
+    Some([
+        u8::from_str_radix(&value[0..2], 16).ok()?,
+        u8::from_str_radix(&value[2..4], 16).ok()?,
+        u8::from_str_radix(&value[4..6], 16).ok()?,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ImageFormat;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_texture_options() {
+        let mut bytes = Cursor::new(Vec::default());
+        RgbaImage::from_fn(4, 2, |x, _| Rgba([x as u8 * 50, 0, 0, 255]))
+            .write_to(&mut bytes, ImageFormat::Png)
+            .unwrap();
+        let bytes = bytes.into_inner();
+        let load = |path: &'static str| {
+            let mut storage = World::default();
+            let entity = storage.spawn((AssetPathStatic::new(path),)).unwrap();
+            TextureAssetProtocol
+                .process_bytes(AssetHandle::new(entity), &mut storage, bytes.clone())
+                .map(|_| storage)
+                .map_err(|error| error.to_string())
+        };
+
+        let storage = load("texture://image.png?cols=2&pad=1").unwrap();
+        let asset = storage.query::<true, &TextureAsset>().next().unwrap();
+        assert_eq!(asset.pages(), 2);
+        assert_eq!(asset.image.dimensions(), (4, 8));
+        assert_eq!(asset.image.get_pixel(0, 4).0, [100, 0, 0, 255]);
+        assert_eq!(asset.region(), Rect::new(0.25, 0.25, 0.5, 0.5));
+        assert_eq!(
+            asset.inset_region(Rect::new(0.5, 0.0, 0.5, 1.0)),
+            Rect::new(0.5, 0.25, 0.25, 0.5)
+        );
+        assert_eq!(asset.sprite("u_image", "image").region, asset.region());
+
+        assert!(load("texture://image.png?pad=x").is_err_and(|error| error.contains("`x`")));
+        assert!(load("texture://image.png?cols=0").is_err_and(|error| error.contains("`cols`")));
+        assert!(load("texture://image.png?mipmaps").is_err());
+        assert!(load("texture://image.png?mipmaps=false").is_ok());
+    }
+}
@@ -0,0 +1,1179 @@
+use crate::{
+    assets::{ensure_dependency, resolve_relative_path},
+    grid_world::{GridWorld, GridWorldLayer},
+};
+use anput::world::World;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use keket::{
+    database::{handle::AssetHandle, path::AssetPathStatic, AssetDatabase},
+    protocol::AssetProtocol,
+};
+use spitfire_draw::{
+    sprite::SpriteTexture,
+    tiles::{TileMap, TileSet, TileSetItem},
+    utils::TextureRef,
+};
+use spitfire_glow::renderer::GlowTextureFiltering;
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io::Read,
+};
+use vek::{Rect, Vec2};
+
+const TILED_GID_MASK: u32 = 0x0FFFFFFF;
+
+#[derive(Debug, Clone, Default)]
+pub struct TiledTileSet {
+    pub first_gid: usize,
+    pub name: String,
+    pub image: Option<AssetPathStatic>,
+    pub image_size: Vec2<usize>,
+    pub tile_size: Vec2<usize>,
+    pub tile_count: usize,
+    pub columns: usize,
+    pub margin: usize,
+    pub spacing: usize,
+    pub colliders: HashSet<usize>,
+}
+
+impl TiledTileSet {
+    pub fn contains(&self, gid: usize) -> bool {
+        gid >= self.first_gid && gid < self.first_gid + self.tile_count
+    }
+
+    pub fn region(&self, id: usize) -> Rect<f32, f32> {
+        let columns = self.columns.max(1);
+        let col = id % columns;
+        let row = id / columns;
+        let x = self.margin + col * (self.tile_size.x + self.spacing);
+        let y = self.margin + row * (self.tile_size.y + self.spacing);
+        let width = self.image_size.x.max(1) as f32;
+        let height = self.image_size.y.max(1) as f32;
+        Rect::new(
+            x as f32 / width,
+            y as f32 / height,
+            self.tile_size.x as f32 / width,
+            self.tile_size.y as f32 / height,
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TiledTileSetSource {
+    Embedded(TiledTileSet),
+    External {
+        first_gid: usize,
+        path: AssetPathStatic,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledTileLayer {
+    pub name: String,
+    pub size: Vec2<usize>,
+    pub tiles: Vec<usize>,
+    pub visible: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TiledObjectShape {
+    Point,
+    Rectangle,
+    Ellipse,
+    Polygon(Vec<Vec2<f32>>),
+    Polyline(Vec<Vec2<f32>>),
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledObject {
+    pub id: usize,
+    pub name: String,
+    pub class: String,
+    pub position: Vec2<f32>,
+    pub size: Vec2<f32>,
+    pub rotation: f32,
+    pub shape: TiledObjectShape,
+    pub properties: HashMap<String, String>,
+}
+
+impl TiledObject {
+    pub fn is_collision(&self) -> bool {
+        self.class.eq_ignore_ascii_case("collision")
+            || self
+                .properties
+                .get("collision")
+                .map(|value| value == "true")
+                .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledObjectLayer {
+    pub name: String,
+    pub class: String,
+    pub objects: Vec<TiledObject>,
+}
+
+impl TiledObjectLayer {
+    pub fn is_collision(&self) -> bool {
+        self.class.eq_ignore_ascii_case("collision") || self.name.eq_ignore_ascii_case("collision")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledSpawnPoint {
+    pub id: usize,
+    pub name: String,
+    pub class: String,
+    pub position: Vec2<f32>,
+    pub properties: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TiledCollisionShape {
+    Rectangle(Rect<f32, f32>),
+    Ellipse {
+        center: Vec2<f32>,
+        radius: Vec2<f32>,
+    },
+    Polygon(Vec<Vec2<f32>>),
+    Polyline(Vec<Vec2<f32>>),
+}
+
+pub struct TiledWorld {
+    pub world: GridWorld,
+    /// Grid worlds of layers using other tileset images, drawn after `world` in order.
+    pub overlays: Vec<GridWorld>,
+    pub spawn_points: Vec<TiledSpawnPoint>,
+    pub collision_shapes: Vec<TiledCollisionShape>,
+}
+
+impl TiledWorld {
+    pub fn spawn_points_of_class<'a>(
+        &'a self,
+        class: &'a str,
+    ) -> impl Iterator<Item = &'a TiledSpawnPoint> {
+        self.spawn_points
+            .iter()
+            .filter(move |point| point.class == class)
+    }
+
+    pub fn spawn_point(&self, name: &str) -> Option<&TiledSpawnPoint> {
+        self.spawn_points.iter().find(|point| point.name == name)
+    }
+}
+
+pub struct TiledTileSetAsset {
+    pub tileset: TiledTileSet,
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledMapAsset {
+    pub size: Vec2<usize>,
+    pub tile_size: Vec2<usize>,
+    pub tilesets: Vec<TiledTileSetSource>,
+    pub tile_layers: Vec<TiledTileLayer>,
+    pub object_layers: Vec<TiledObjectLayer>,
+}
+
+impl TiledMapAsset {
+    pub fn dependencies(&self) -> impl Iterator<Item = AssetPathStatic> + '_ {
+        self.tilesets.iter().filter_map(|source| match source {
+            TiledTileSetSource::Embedded(tileset) => tileset.image.clone(),
+            TiledTileSetSource::External { path, .. } => Some(path.clone()),
+        })
+    }
+
+    pub fn resolve_tilesets(
+        &self,
+        assets: &AssetDatabase,
+    ) -> Result<Vec<TiledTileSet>, Box<dyn Error>> {
+        self.tilesets
+            .iter()
+            .map(|source| -> Result<TiledTileSet, Box<dyn Error>> {
+                match source {
+                    TiledTileSetSource::Embedded(tileset) => Ok(tileset.clone()),
+                    TiledTileSetSource::External { first_gid, path } => {
+                        let handle = assets
+                            .find(path.clone())
+                            .ok_or_else(|| format!("Tiled tileset: `{}` is not loaded", path))?;
+                        let asset = handle
+                            .access_checked::<&TiledTileSetAsset>(assets)
+                            .ok_or_else(|| format!("Tiled tileset: `{}` is not ready", path))?;
+                        Ok(TiledTileSet {
+                            first_gid: *first_gid,
+                            ..asset.tileset.clone()
+                        })
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Grid world renders tiles from single texture, so consecutive tile layers using
+    /// same tileset image share one grid world, and each layer can use only one image.
+    /// Hidden layers are kept after visible ones, outside of visible layers range.
+    /// Colliders of all layers are put into first grid world.
+    pub fn build_world(
+        &self,
+        assets: &AssetDatabase,
+        tile_size: Vec2<f32>,
+    ) -> Result<TiledWorld, Box<dyn Error>> {
+        let tilesets = self.resolve_tilesets(assets)?;
+        let mut mappings = HashMap::new();
+        let mut colliders = HashSet::new();
+        for source in &tilesets {
+            for id in 0..source.tile_count {
+                let size = Vec2::new(
+                    source.tile_size.x.div_ceil(self.tile_size.x.max(1)),
+                    source.tile_size.y.div_ceil(self.tile_size.y.max(1)),
+                );
+                mappings.insert(
+                    source.first_gid + id,
+                    TileSetItem {
+                        region: source.region(id),
+                        size,
+                        ..Default::default()
+                    },
+                );
+            }
+            colliders.extend(source.colliders.iter().map(|id| source.first_gid + id));
+        }
+
+        let mut runs = Vec::<(Option<&str>, Vec<&TiledTileLayer>)>::new();
+        for layer in &self.tile_layers {
+            let mut images = layer
+                .tiles
+                .iter()
+                .filter(|id| **id != 0)
+                .filter_map(|id| tilesets.iter().find(|tileset| tileset.contains(*id)))
+                .filter_map(|tileset| tileset.image.as_ref())
+                .map(|image| image.path())
+                .collect::<Vec<_>>();
+            images.sort();
+            images.dedup();
+            if images.len() > 1 {
+                return Err(format!(
+                    "Tiled layer: `{}` uses multiple tileset images, while grid world supports only one: {:?}",
+                    layer.name, images
+                )
+                .into());
+            }
+            let image = images.first().copied();
+            match runs.last_mut() {
+                Some((current, layers)) if image.is_none() || *current == image => {
+                    layers.push(layer);
+                }
+                Some((current @ None, layers)) => {
+                    *current = image;
+                    layers.push(layer);
+                }
+                _ => runs.push((image, vec![layer])),
+            }
+        }
+        if runs.is_empty() {
+            runs.push((None, vec![]));
+        }
+
+        let mut worlds = runs
+            .into_iter()
+            .map(|(image, layers)| -> Result<GridWorld, Box<dyn Error>> {
+                let tileset = match image {
+                    Some(image) => TileSet::single(SpriteTexture {
+                        sampler: "u_image".into(),
+                        texture: TextureRef::name(image.to_owned()),
+                        filtering: GlowTextureFiltering::Nearest,
+                    }),
+                    None => TileSet::default(),
+                }
+                .mappings(mappings.clone());
+                let visible_count = layers.iter().filter(|layer| layer.visible).count();
+                let mut layers = layers
+                    .iter()
+                    .filter(|layer| layer.visible)
+                    .chain(layers.iter().filter(|layer| !layer.visible))
+                    .map(|layer| {
+                        TileMap::with_buffer(layer.size, layer.tiles.clone())
+                            .map(GridWorldLayer::new)
+                            .ok_or_else(|| {
+                                format!("Tiled layer: `{}` has invalid size", layer.name)
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into_iter();
+                let mut world = GridWorld::new(
+                    tile_size,
+                    tileset,
+                    layers
+                        .next()
+                        .unwrap_or_else(|| GridWorldLayer::new(TileMap::new(self.size, 0))),
+                );
+                for layer in layers {
+                    world = world.with_layer(layer);
+                }
+                Ok(world.with_visible_layers(0..visible_count))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let mut world = worlds.next().unwrap();
+        let overlays = worlds.collect();
+        for layer in &self.tile_layers {
+            for (index, id) in layer.tiles.iter().enumerate() {
+                if colliders.contains(id) {
+                    world.set_collider(
+                        Vec2::new(index % layer.size.x.max(1), index / layer.size.x.max(1)),
+                        true,
+                    );
+                }
+            }
+        }
+
+        let scale = tile_size / Vec2::new(self.tile_size.x as f32, self.tile_size.y as f32);
+        let mut spawn_points = vec![];
+        let mut collision_shapes = vec![];
+        for layer in &self.object_layers {
+            for object in &layer.objects {
+                let position = object.position * scale;
+                let size = object.size * scale;
+                let points = |points: &[Vec2<f32>]| {
+                    points
+                        .iter()
+                        .map(|point| position + *point * scale)
+                        .collect::<Vec<_>>()
+                };
+                if object.shape != TiledObjectShape::Point
+                    && (layer.is_collision() || object.is_collision())
+                {
+                    collision_shapes.push(match &object.shape {
+                        TiledObjectShape::Rectangle | TiledObjectShape::Point => {
+                            TiledCollisionShape::Rectangle(Rect::new(
+                                position.x, position.y, size.x, size.y,
+                            ))
+                        }
+                        TiledObjectShape::Ellipse => TiledCollisionShape::Ellipse {
+                            center: position + size * 0.5,
+                            radius: size * 0.5,
+                        },
+                        TiledObjectShape::Polygon(items) => {
+                            TiledCollisionShape::Polygon(points(items))
+                        }
+                        TiledObjectShape::Polyline(items) => {
+                            TiledCollisionShape::Polyline(points(items))
+                        }
+                    });
+                } else {
+                    spawn_points.push(TiledSpawnPoint {
+                        id: object.id,
+                        name: object.name.to_owned(),
+                        class: object.class.to_owned(),
+                        position: match object.shape {
+                            TiledObjectShape::Rectangle | TiledObjectShape::Ellipse => {
+                                position + size * 0.5
+                            }
+                            _ => position,
+                        },
+                        properties: object.properties.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(TiledWorld {
+            world,
+            overlays,
+            spawn_points,
+            collision_shapes,
+        })
+    }
+}
+
+pub struct TiledAssetProtocol;
+
+impl AssetProtocol for TiledAssetProtocol {
+    fn name(&self) -> &str {
+        "tiled"
+    }
+
+    fn process_bytes(
+        &mut self,
+        handle: AssetHandle,
+        storage: &mut World,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error>> {
+        let path = storage.component::<true, AssetPathStatic>(handle.entity())?;
+        let base = path.path().to_owned();
+        drop(path);
+        let content = std::str::from_utf8(&bytes)?;
+
+        match parse_document(content, &base)? {
+            TiledDocument::Map(asset) => {
+                for dependency in asset.dependencies() {
+                    ensure_dependency(storage, handle, dependency)?;
+                }
+                storage.insert(handle.entity(), (asset,))?;
+            }
+            TiledDocument::TileSet(tileset) => {
+                if let Some(image) = tileset.image.clone() {
+                    ensure_dependency(storage, handle, image)?;
+                }
+                storage.insert(handle.entity(), (TiledTileSetAsset { tileset },))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+enum TiledDocument {
+    Map(TiledMapAsset),
+    TileSet(TiledTileSet),
+}
+
+fn parse_document(content: &str, base: &str) -> Result<TiledDocument, Box<dyn Error>> {
+    if content.trim_start().starts_with('<') {
+        tmx::parse(content, base)
+    } else {
+        json::parse(content, base)
+    }
+}
+
+fn texture_path(base: &str, image: &str) -> AssetPathStatic {
+    AssetPathStatic::new(format!("texture://{}", resolve_relative_path(base, image)))
+}
+
+fn tileset_path(base: &str, source: &str) -> AssetPathStatic {
+    AssetPathStatic::new(format!("tiled://{}", resolve_relative_path(base, source)))
+}
+
+fn decode_tiles(
+    data: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<usize>, Box<dyn Error>> {
+    match encoding.unwrap_or("csv") {
+        "csv" => data
+            .split(',')
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .map(|item| Ok(item.parse::<u32>()? & TILED_GID_MASK).map(|gid| gid as usize))
+            .collect(),
+        "base64" => {
+            let bytes = BASE64.decode(data.split_whitespace().collect::<String>())?;
+            let bytes = match compression.unwrap_or_default() {
+                "" => bytes,
+                "zlib" => {
+                    let mut result = vec![];
+                    ZlibDecoder::new(bytes.as_slice()).read_to_end(&mut result)?;
+                    result
+                }
+                "gzip" => {
+                    let mut result = vec![];
+                    GzDecoder::new(bytes.as_slice()).read_to_end(&mut result)?;
+                    result
+                }
+                compression => {
+                    return Err(format!("Unsupported Tiled compression: `{}`", compression).into())
+                }
+            };
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|chunk| {
+                    (u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) & TILED_GID_MASK)
+                        as usize
+                })
+                .collect())
+        }
+        encoding => Err(format!("Unsupported Tiled encoding: `{}`", encoding).into()),
+    }
+}
+
+fn validate_tiles(layer: &TiledTileLayer) -> Result<(), Box<dyn Error>> {
+    if layer.tiles.len() == layer.size.x * layer.size.y {
+        Ok(())
+    } else {
+        Err(format!(
+            "Tiled layer: `{}` has {} tiles, expected {}",
+            layer.name,
+            layer.tiles.len(),
+            layer.size.x * layer.size.y
+        )
+        .into())
+    }
+}
+
+mod json {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    #[derive(Deserialize)]
+    struct Property {
+        name: String,
+        value: Value,
+    }
+
+    fn properties(items: Vec<Property>) -> HashMap<String, String> {
+        items
+            .into_iter()
+            .map(|item| {
+                let value = match item.value {
+                    Value::String(value) => value,
+                    value => value.to_string(),
+                };
+                (item.name, value)
+            })
+            .collect()
+    }
+
+    #[derive(Deserialize)]
+    struct Point {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Deserialize)]
+    struct Object {
+        #[serde(default)]
+        id: usize,
+        #[serde(default)]
+        name: String,
+        #[serde(default, alias = "type")]
+        class: String,
+        #[serde(default)]
+        x: f32,
+        #[serde(default)]
+        y: f32,
+        #[serde(default)]
+        width: f32,
+        #[serde(default)]
+        height: f32,
+        #[serde(default)]
+        rotation: f32,
+        #[serde(default)]
+        point: bool,
+        #[serde(default)]
+        ellipse: bool,
+        polygon: Option<Vec<Point>>,
+        polyline: Option<Vec<Point>>,
+        #[serde(default)]
+        properties: Vec<Property>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Data {
+        Tiles(Vec<u32>),
+        Encoded(String),
+    }
+
+    #[derive(Deserialize)]
+    struct Layer {
+        #[serde(rename = "type")]
+        kind: String,
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        class: String,
+        #[serde(default)]
+        width: usize,
+        #[serde(default)]
+        height: usize,
+        #[serde(default = "default_visible")]
+        visible: bool,
+        data: Option<Data>,
+        encoding: Option<String>,
+        compression: Option<String>,
+        #[serde(default)]
+        objects: Vec<Object>,
+        #[serde(default)]
+        layers: Vec<Layer>,
+    }
+
+    fn default_visible() -> bool {
+        true
+    }
+
+    #[derive(Deserialize)]
+    struct Tile {
+        id: usize,
+        #[serde(default)]
+        properties: Vec<Property>,
+    }
+
+    #[derive(Deserialize)]
+    struct TileSet {
+        #[serde(default)]
+        firstgid: usize,
+        source: Option<String>,
+        #[serde(default)]
+        name: String,
+        image: Option<String>,
+        #[serde(default)]
+        imagewidth: usize,
+        #[serde(default)]
+        imageheight: usize,
+        #[serde(default)]
+        tilewidth: usize,
+        #[serde(default)]
+        tileheight: usize,
+        #[serde(default)]
+        tilecount: usize,
+        #[serde(default)]
+        columns: usize,
+        #[serde(default)]
+        margin: usize,
+        #[serde(default)]
+        spacing: usize,
+        #[serde(default)]
+        tiles: Vec<Tile>,
+    }
+
+    #[derive(Deserialize)]
+    struct Map {
+        #[serde(default)]
+        orientation: String,
+        #[serde(default)]
+        infinite: bool,
+        width: usize,
+        height: usize,
+        tilewidth: usize,
+        tileheight: usize,
+        #[serde(default)]
+        layers: Vec<Layer>,
+        #[serde(default)]
+        tilesets: Vec<TileSet>,
+    }
+
+    #[derive(Deserialize)]
+    struct Document {
+        #[serde(rename = "type", default)]
+        kind: String,
+    }
+
+    pub(super) fn parse(content: &str, base: &str) -> Result<TiledDocument, Box<dyn Error>> {
+        if serde_json::from_str::<Document>(content)?.kind == "tileset" {
+            let tileset = serde_json::from_str::<TileSet>(content)?;
+            return Ok(TiledDocument::TileSet(tileset_from(tileset, base)));
+        }
+        let map = serde_json::from_str::<Map>(content)?;
+        if map.orientation != "orthogonal" {
+            return Err(format!("Unsupported Tiled map orientation: `{}`", map.orientation).into());
+        }
+        if map.infinite {
+            return Err("Infinite Tiled maps are not supported".into());
+        }
+        let tilesets = map
+            .tilesets
+            .into_iter()
+            .map(|tileset| match tileset.source.as_deref() {
+                Some(source) => TiledTileSetSource::External {
+                    first_gid: tileset.firstgid,
+                    path: tileset_path(base, source),
+                },
+                None => TiledTileSetSource::Embedded(tileset_from(tileset, base)),
+            })
+            .collect();
+        let mut tile_layers = vec![];
+        let mut object_layers = vec![];
+        collect_layers(map.layers, &mut tile_layers, &mut object_layers)?;
+        Ok(TiledDocument::Map(TiledMapAsset {
+            size: Vec2::new(map.width, map.height),
+            tile_size: Vec2::new(map.tilewidth, map.tileheight),
+            tilesets,
+            tile_layers,
+            object_layers,
+        }))
+    }
+
+    fn tileset_from(tileset: TileSet, base: &str) -> TiledTileSet {
+        TiledTileSet {
+            first_gid: tileset.firstgid,
+            name: tileset.name,
+            image: tileset.image.map(|image| texture_path(base, &image)),
+            image_size: Vec2::new(tileset.imagewidth, tileset.imageheight),
+            tile_size: Vec2::new(tileset.tilewidth, tileset.tileheight),
+            tile_count: tileset.tilecount,
+            columns: tileset.columns,
+            margin: tileset.margin,
+            spacing: tileset.spacing,
+            colliders: tileset
+                .tiles
+                .into_iter()
+                .filter(|tile| {
+                    tile.properties.iter().any(|property| {
+                        property.name == "collision"
+                            && (property.value == Value::Bool(true) || property.value == "true")
+                    })
+                })
+                .map(|tile| tile.id)
+                .collect(),
+        }
+    }
+
+    fn collect_layers(
+        layers: Vec<Layer>,
+        tile_layers: &mut Vec<TiledTileLayer>,
+        object_layers: &mut Vec<TiledObjectLayer>,
+    ) -> Result<(), Box<dyn Error>> {
+        for layer in layers {
+            match layer.kind.as_str() {
+                "tilelayer" => {
+                    let tiles = match layer.data {
+                        Some(Data::Tiles(tiles)) => tiles
+                            .into_iter()
+                            .map(|gid| (gid & TILED_GID_MASK) as usize)
+                            .collect(),
+                        Some(Data::Encoded(data)) => decode_tiles(
+                            &data,
+                            layer.encoding.as_deref(),
+                            layer.compression.as_deref(),
+                        )?,
+                        None => vec![],
+                    };
+                    let layer = TiledTileLayer {
+                        name: layer.name,
+                        size: Vec2::new(layer.width, layer.height),
+                        tiles,
+                        visible: layer.visible,
+                    };
+                    validate_tiles(&layer)?;
+                    tile_layers.push(layer);
+                }
+                "objectgroup" => object_layers.push(TiledObjectLayer {
+                    name: layer.name,
+                    class: layer.class,
+                    objects: layer.objects.into_iter().map(object_from).collect(),
+                }),
+                "group" => collect_layers(layer.layers, tile_layers, object_layers)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn object_from(object: Object) -> TiledObject {
+        let points = |points: Vec<Point>| {
+            points
+                .into_iter()
+                .map(|point| Vec2::new(point.x, point.y))
+                .collect()
+        };
+        let shape = if object.point {
+            TiledObjectShape::Point
+        } else if object.ellipse {
+            TiledObjectShape::Ellipse
+        } else if let Some(polygon) = object.polygon {
+            TiledObjectShape::Polygon(points(polygon))
+        } else if let Some(polyline) = object.polyline {
+            TiledObjectShape::Polyline(points(polyline))
+        } else {
+            TiledObjectShape::Rectangle
+        };
+        TiledObject {
+            id: object.id,
+            name: object.name,
+            class: object.class,
+            position: Vec2::new(object.x, object.y),
+            size: Vec2::new(object.width, object.height),
+            rotation: object.rotation,
+            shape,
+            properties: properties(object.properties),
+        }
+    }
+}
+
+mod tmx {
+    use super::*;
+    use roxmltree::{Document, Node};
+
+    fn attribute<T: std::str::FromStr>(node: Node, name: &str) -> Option<T> {
+        node.attribute(name)?.parse().ok()
+    }
+
+    fn required<T: std::str::FromStr>(node: Node, name: &str) -> Result<T, Box<dyn Error>> {
+        attribute(node, name).ok_or_else(|| {
+            format!(
+                "Tiled element: `{}` has missing or invalid attribute: `{}`",
+                node.tag_name().name(),
+                name
+            )
+            .into()
+        })
+    }
+
+    fn children<'a, 'input>(
+        node: Node<'a, 'input>,
+        name: &'a str,
+    ) -> impl Iterator<Item = Node<'a, 'input>> {
+        node.children()
+            .filter(move |child| child.is_element() && child.tag_name().name() == name)
+    }
+
+    fn properties(node: Node) -> HashMap<String, String> {
+        children(node, "properties")
+            .flat_map(|properties| children(properties, "property"))
+            .filter_map(|property| {
+                let name = property.attribute("name")?.to_owned();
+                let value = property
+                    .attribute("value")
+                    .or_else(|| property.text())
+                    .unwrap_or_default()
+                    .to_owned();
+                Some((name, value))
+            })
+            .collect()
+    }
+
+    fn class(node: Node) -> String {
+        node.attribute("class")
+            .or_else(|| node.attribute("type"))
+            .unwrap_or_default()
+            .to_owned()
+    }
+
+    pub(super) fn parse(content: &str, base: &str) -> Result<TiledDocument, Box<dyn Error>> {
+        let document = Document::parse(content)?;
+        let root = document.root_element();
+        match root.tag_name().name() {
+            "tileset" => Ok(TiledDocument::TileSet(tileset_from(root, 0, base)?)),
+            "map" => parse_map(root, base).map(TiledDocument::Map),
+            name => Err(format!("Unsupported Tiled document: `{}`", name).into()),
+        }
+    }
+
+    fn parse_map(root: Node, base: &str) -> Result<TiledMapAsset, Box<dyn Error>> {
+        let orientation = root.attribute("orientation").unwrap_or_default();
+        if orientation != "orthogonal" {
+            return Err(format!("Unsupported Tiled map orientation: `{}`", orientation).into());
+        }
+        if root.attribute("infinite") == Some("1") {
+            return Err("Infinite Tiled maps are not supported".into());
+        }
+        let tilesets = children(root, "tileset")
+            .map(|node| -> Result<TiledTileSetSource, Box<dyn Error>> {
+                let first_gid = required(node, "firstgid")?;
+                Ok(match node.attribute("source") {
+                    Some(source) => TiledTileSetSource::External {
+                        first_gid,
+                        path: tileset_path(base, source),
+                    },
+                    None => TiledTileSetSource::Embedded(tileset_from(node, first_gid, base)?),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut tile_layers = vec![];
+        let mut object_layers = vec![];
+        collect_layers(root, &mut tile_layers, &mut object_layers)?;
+        Ok(TiledMapAsset {
+            size: Vec2::new(required(root, "width")?, required(root, "height")?),
+            tile_size: Vec2::new(required(root, "tilewidth")?, required(root, "tileheight")?),
+            tilesets,
+            tile_layers,
+            object_layers,
+        })
+    }
+
+    fn tileset_from(
+        node: Node,
+        first_gid: usize,
+        base: &str,
+    ) -> Result<TiledTileSet, Box<dyn Error>> {
+        let image = children(node, "image").next();
+        Ok(TiledTileSet {
+            first_gid,
+            name: node.attribute("name").unwrap_or_default().to_owned(),
+            image: image
+                .and_then(|image| image.attribute("source"))
+                .map(|source| texture_path(base, source)),
+            image_size: image
+                .map(|image| {
+                    Vec2::new(
+                        attribute(image, "width").unwrap_or_default(),
+                        attribute(image, "height").unwrap_or_default(),
+                    )
+                })
+                .unwrap_or_default(),
+            tile_size: Vec2::new(required(node, "tilewidth")?, required(node, "tileheight")?),
+            tile_count: attribute(node, "tilecount").unwrap_or_default(),
+            columns: attribute(node, "columns").unwrap_or_default(),
+            margin: attribute(node, "margin").unwrap_or_default(),
+            spacing: attribute(node, "spacing").unwrap_or_default(),
+            colliders: children(node, "tile")
+                .filter(|tile| {
+                    properties(*tile)
+                        .get("collision")
+                        .map(|value| value == "true")
+                        .unwrap_or_default()
+                })
+                .filter_map(|tile| attribute(tile, "id"))
+                .collect(),
+        })
+    }
+
+    fn collect_layers(
+        node: Node,
+        tile_layers: &mut Vec<TiledTileLayer>,
+        object_layers: &mut Vec<TiledObjectLayer>,
+    ) -> Result<(), Box<dyn Error>> {
+        for child in node.children().filter(|child| child.is_element()) {
+            match child.tag_name().name() {
+                "layer" => {
+                    let data = children(child, "data")
+                        .next()
+                        .ok_or("Tiled layer has no data")?;
+                    let tiles = if data.attribute("encoding").is_some() {
+                        decode_tiles(
+                            data.text().unwrap_or_default(),
+                            data.attribute("encoding"),
+                            data.attribute("compression"),
+                        )?
+                    } else {
+                        children(data, "tile")
+                            .map(|tile| {
+                                (attribute::<u32>(tile, "gid").unwrap_or_default() & TILED_GID_MASK)
+                                    as usize
+                            })
+                            .collect()
+                    };
+                    let layer = TiledTileLayer {
+                        name: child.attribute("name").unwrap_or_default().to_owned(),
+                        size: Vec2::new(required(child, "width")?, required(child, "height")?),
+                        tiles,
+                        visible: child.attribute("visible") != Some("0"),
+                    };
+                    validate_tiles(&layer)?;
+                    tile_layers.push(layer);
+                }
+                "objectgroup" => object_layers.push(TiledObjectLayer {
+                    name: child.attribute("name").unwrap_or_default().to_owned(),
+                    class: class(child),
+                    objects: children(child, "object").map(object_from).collect(),
+                }),
+                "group" => collect_layers(child, tile_layers, object_layers)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn points(node: Node) -> Vec<Vec2<f32>> {
+        node.attribute("points")
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter_map(|point| {
+                let (x, y) = point.split_once(',')?;
+                Some(Vec2::new(x.parse().ok()?, y.parse().ok()?))
+            })
+            .collect()
+    }
+
+    fn object_from(node: Node) -> TiledObject {
+        let shape = if children(node, "point").next().is_some() {
+            TiledObjectShape::Point
+        } else if children(node, "ellipse").next().is_some() {
+            TiledObjectShape::Ellipse
+        } else if let Some(polygon) = children(node, "polygon").next() {
+            TiledObjectShape::Polygon(points(polygon))
+        } else if let Some(polyline) = children(node, "polyline").next() {
+            TiledObjectShape::Polyline(points(polyline))
+        } else {
+            TiledObjectShape::Rectangle
+        };
+        TiledObject {
+            id: attribute(node, "id").unwrap_or_default(),
+            name: node.attribute("name").unwrap_or_default().to_owned(),
+            class: class(node),
+            position: Vec2::new(
+                attribute(node, "x").unwrap_or_default(),
+                attribute(node, "y").unwrap_or_default(),
+            ),
+            size: Vec2::new(
+                attribute(node, "width").unwrap_or_default(),
+                attribute(node, "height").unwrap_or_default(),
+            ),
+            rotation: attribute(node, "rotation").unwrap_or_default(),
+            shape,
+            properties: properties(node),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_document, TiledCollisionShape, TiledDocument, TiledObjectShape, TiledTileSetSource,
+    };
+    use keket::database::AssetDatabase;
+    use spitfire_draw::utils::TextureRef;
+    use vek::{Rect, Vec2};
+
+    const JSON: &str = r#"{
+        "type": "map", "orientation": "orthogonal", "infinite": false,
+        "width": 2, "height": 2, "tilewidth": 16, "tileheight": 16,
+        "tilesets": [
+            { "firstgid": 1, "name": "terrain", "image": "../images/terrain.png",
+              "imagewidth": 32, "imageheight": 16, "tilewidth": 16, "tileheight": 16,
+              "tilecount": 2, "columns": 2,
+              "tiles": [{ "id": 1, "properties": [{ "name": "collision", "type": "bool", "value": true }] }] },
+            { "firstgid": 3, "source": "props.tsj" }
+        ],
+        "layers": [
+            { "type": "tilelayer", "name": "ground", "width": 2, "height": 2, "data": [1, 2, 2147483649, 0] },
+            { "type": "objectgroup", "name": "spawns", "objects": [
+                { "id": 1, "name": "player", "type": "spawn", "x": 8, "y": 8, "point": true }
+            ] },
+            { "type": "objectgroup", "name": "collision", "objects": [
+                { "id": 2, "x": 0, "y": 0, "width": 16, "height": 32 }
+            ] }
+        ]
+    }"#;
+
+    const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <map orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16" infinite="0">
+            <tileset firstgid="1" name="terrain" tilewidth="16" tileheight="16" tilecount="2" columns="2">
+                <image source="../images/terrain.png" width="32" height="16"/>
+                <tile id="1"><properties><property name="collision" type="bool" value="true"/></properties></tile>
+            </tileset>
+            <tileset firstgid="3" source="props.tsx"/>
+            <layer name="ground" width="2" height="2">
+                <data encoding="csv">1,2,2147483649,0</data>
+            </layer>
+            <objectgroup name="spawns">
+                <object id="1" name="player" type="spawn" x="8" y="8"><point/></object>
+            </objectgroup>
+            <objectgroup name="collision">
+                <object id="2" x="0" y="0" width="16" height="32"/>
+            </objectgroup>
+        </map>"#;
+
+    #[test]
+    fn test_tiled_map() {
+        for (content, tileset) in [(JSON, "maps/props.tsj"), (TMX, "maps/props.tsx")] {
+            let Ok(TiledDocument::Map(map)) = parse_document(content, "maps/level.tmx") else {
+                panic!("Expected Tiled map");
+            };
+            assert_eq!(map.size, Vec2::new(2, 2));
+            assert_eq!(map.tile_size, Vec2::new(16, 16));
+            assert_eq!(map.tilesets.len(), 2);
+            match &map.tilesets[0] {
+                TiledTileSetSource::Embedded(embedded) => {
+                    let image = embedded.image.as_ref().unwrap();
+                    assert_eq!(image.protocol(), "texture");
+                    assert_eq!(image.path(), "images/terrain.png");
+                    assert!(embedded.colliders.contains(&1));
+                }
+                _ => panic!("Expected embedded tileset"),
+            }
+            match &map.tilesets[1] {
+                TiledTileSetSource::External { first_gid, path } => {
+                    assert_eq!(*first_gid, 3);
+                    assert_eq!(path.protocol(), "tiled");
+                    assert_eq!(path.path(), tileset);
+                }
+                _ => panic!("Expected external tileset"),
+            }
+            assert_eq!(map.tile_layers[0].tiles, vec![1, 2, 1, 0]);
+            assert_eq!(map.object_layers.len(), 2);
+            assert_eq!(
+                map.object_layers[0].objects[0].shape,
+                TiledObjectShape::Point
+            );
+            assert_eq!(map.object_layers[0].objects[0].class, "spawn");
+            assert!(map.object_layers[1].is_collision());
+        }
+    }
+
+    #[test]
+    fn test_tiled_build_world() {
+        let content = r#"{
+            "type": "map", "orientation": "orthogonal", "infinite": false,
+            "width": 2, "height": 2, "tilewidth": 16, "tileheight": 16,
+            "tilesets": [
+                { "firstgid": 1, "name": "terrain", "image": "terrain.png",
+                  "imagewidth": 32, "imageheight": 16, "tilewidth": 16, "tileheight": 16,
+                  "tilecount": 2, "columns": 2,
+                  "tiles": [{ "id": 1, "properties": [{ "name": "collision", "type": "bool", "value": true }] }] },
+                { "firstgid": 3, "name": "props", "image": "props.png",
+                  "imagewidth": 32, "imageheight": 16, "tilewidth": 16, "tileheight": 16,
+                  "tilecount": 2, "columns": 2 }
+            ],
+            "layers": [
+                { "type": "tilelayer", "name": "ground", "width": 2, "height": 2, "data": [1, 2, 1, 1] },
+                { "type": "tilelayer", "name": "blockers", "width": 2, "height": 2, "data": [0, 0, 2, 0], "visible": false },
+                { "type": "tilelayer", "name": "decals", "width": 2, "height": 2, "data": [0, 0, 0, 1] },
+                { "type": "tilelayer", "name": "props", "width": 2, "height": 2, "data": [3, 0, 0, 4] },
+                { "type": "objectgroup", "name": "spawns", "objects": [
+                    { "id": 1, "name": "player", "type": "spawn", "x": 8, "y": 8, "point": true },
+                    { "id": 2, "name": "chest", "type": "item", "x": 16, "y": 0, "width": 16, "height": 16 }
+                ] },
+                { "type": "objectgroup", "name": "collision", "objects": [
+                    { "id": 3, "x": 0, "y": 0, "width": 16, "height": 32 }
+                ] }
+            ]
+        }"#;
+        let Ok(TiledDocument::Map(map)) = parse_document(content, "maps/level.tmj") else {
+            panic!("Expected Tiled map");
+        };
+        let result = map
+            .build_world(&AssetDatabase::default(), 32.0.into())
+            .unwrap();
+
+        let world = &result.world;
+        assert_eq!(world.layers().len(), 3);
+        assert_eq!(world.visible_layers, 0..2);
+        assert_eq!(world.layers()[1].tilemap.buffer(), &[0, 0, 0, 1]);
+        assert_eq!(world.layers()[2].tilemap.buffer(), &[0, 0, 2, 0]);
+        assert_eq!(
+            world.tileset.mappings[&2].region,
+            Rect::new(0.5, 0.0, 0.5, 1.0)
+        );
+        assert!(matches!(
+            &world.tileset.textures[0].texture,
+            TextureRef::Name(name) if name == "maps/terrain.png"
+        ));
+        assert!(world.collider(Vec2::new(1, 0)));
+        assert!(world.collider(Vec2::new(0, 1)));
+        assert!(!world.collider(Vec2::new(1, 1)));
+
+        assert_eq!(result.overlays.len(), 1);
+        assert_eq!(result.overlays[0].visible_layers, 0..1);
+        assert_eq!(
+            result.overlays[0].layers()[0].tilemap.buffer(),
+            &[3, 0, 0, 4]
+        );
+        assert!(matches!(
+            &result.overlays[0].tileset.textures[0].texture,
+            TextureRef::Name(name) if name == "maps/props.png"
+        ));
+
+        assert_eq!(result.spawn_points.len(), 2);
+        assert_eq!(
+            result.spawn_point("player").unwrap().position,
+            Vec2::new(16.0, 16.0)
+        );
+        assert_eq!(
+            result
+                .spawn_points_of_class("item")
+                .next()
+                .unwrap()
+                .position,
+            Vec2::new(48.0, 16.0)
+        );
+        assert_eq!(
+            result.collision_shapes,
+            vec![TiledCollisionShape::Rectangle(Rect::new(
+                0.0, 0.0, 32.0, 64.0
+            ))]
+        );
+
+        let mixed = content.replace("[0, 0, 0, 1]", "[0, 0, 3, 1]");
+        let Ok(TiledDocument::Map(map)) = parse_document(&mixed, "maps/level.tmj") else {
+            panic!("Expected Tiled map");
+        };
+        assert!(map
+            .build_world(&AssetDatabase::default(), 32.0.into())
+            .is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::audio::spatial::AudioEmitterId;
+use kira::sound::static_sound::StaticSoundData;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundGroup {
+    /// Sound names, where trailing `*` matches any sound name with given prefix.
+    pub sounds: Vec<String>,
+    pub volume: [f64; 2],
+    pub pitch: [f64; 2],
+    /// Max number of concurrently playing variations, zero means unlimited.
+    pub max_voices: usize,
+    /// Seconds to wait before group can be played again.
+    pub cooldown: f32,
+    pub bus: Option<String>,
+}
+
+impl Default for SoundGroup {
+    fn default() -> Self {
+        Self {
+            sounds: Default::default(),
+            volume: [1.0, 1.0],
+            pitch: [1.0, 1.0],
+            max_voices: 0,
+            cooldown: 0.0,
+            bus: None,
+        }
+    }
+}
+
+impl SoundGroup {
+    pub fn pattern(pattern: impl ToString) -> Self {
+        Self {
+            sounds: vec![pattern.to_string()],
+            ..Default::default()
+        }
+    }
+
+    pub fn with_sound(mut self, name: impl ToString) -> Self {
+        self.sounds.push(name.to_string());
+        self
+    }
+
+    pub fn with_volume(mut self, min: f64, max: f64) -> Self {
+        self.volume = [min, max];
+        self
+    }
+
+    pub fn with_pitch(mut self, min: f64, max: f64) -> Self {
+        self.pitch = [min, max];
+        self
+    }
+
+    pub fn with_max_voices(mut self, value: usize) -> Self {
+        self.max_voices = value;
+        self
+    }
+
+    pub fn with_cooldown(mut self, value: f32) -> Self {
+        self.cooldown = value;
+        self
+    }
+
+    pub fn with_bus(mut self, value: impl ToString) -> Self {
+        self.bus = Some(value.to_string());
+        self
+    }
+
+    pub fn variations<'a>(&self, sounds: &'a HashMap<String, StaticSoundData>) -> Vec<&'a str> {
+        let mut result = sounds
+            .keys()
+            .filter(|name| {
+                self.sounds
+                    .iter()
+                    .any(|pattern| match pattern.strip_suffix('*') {
+                        Some(prefix) => name.starts_with(prefix),
+                        None => *name == pattern,
+                    })
+            })
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>();
+        result.sort();
+        result
+    }
+
+    pub fn random_volume(&self) -> f64 {
+        random_in_range(self.volume)
+    }
+
+    pub fn random_pitch(&self) -> f64 {
+        random_in_range(self.pitch)
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SoundGroupManifest {
+    pub groups: HashMap<String, SoundGroup>,
+}
+
+impl SoundGroupManifest {
+    pub fn load_from_str(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(toml::from_str(content)?)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SoundGroupState {
+    pub voices: Vec<AudioEmitterId>,
+    pub cooldown: f32,
+    pub last_variation: Option<usize>,
+}
+
+impl SoundGroupState {
+    pub fn pick_variation(&mut self, count: usize) -> Option<usize> {
+        let result = match count {
+            0 => return None,
+            1 => 0,
+            _ => {
+                let index = thread_rng().gen_range(0..(count - 1));
+                match self.last_variation {
+                    Some(last) if index >= last => index + 1,
+                    _ => index,
+                }
+            }
+        };
+        self.last_variation = Some(result);
+        Some(result)
+    }
+}
+
+fn random_in_range([min, max]: [f64; 2]) -> f64 {
+    if min < max {
+        thread_rng().gen_range(min..=max)
+    } else {
+        min
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sound_group() {
+        let manifest = SoundGroupManifest::load_from_str(
+            r#"
+            [footstep]
+            sounds = ["footstep/grass/*"]
+            pitch = [0.9, 1.1]
+
+            [sword]
+            sounds = ["sword"]
+            max_voices = 2
+            cooldown = 0.1
+            "#,
+        )
+        .unwrap();
+        assert_eq!(manifest.groups["footstep"].pitch, [0.9, 1.1]);
+        assert_eq!(manifest.groups["footstep"].volume, [1.0, 1.0]);
+        assert_eq!(manifest.groups["sword"].max_voices, 2);
+
+        let mut state = SoundGroupState::default();
+        for _ in 0..100 {
+            let last = state.last_variation;
+            let index = state.pick_variation(3).unwrap();
+            assert!(index < 3);
+            assert_ne!(Some(index), last);
+        }
+        assert_eq!(state.pick_variation(1), Some(0));
+        assert_eq!(state.pick_variation(0), None);
+    }
+}
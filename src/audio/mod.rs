@@ -0,0 +1,489 @@
+pub mod group;
+pub mod music;
+pub mod sfx;
+pub mod spatial;
+
+use crate::{
+    audio::{
+        group::{SoundGroup, SoundGroupState},
+        music::MusicDirector,
+        spatial::{AudioEmitter, AudioEmitterId, AudioListener},
+    },
+    grid_world::GridWorld,
+};
+use kira::{
+    manager::{
+        backend::{
+            mock::{MockBackend, MockBackendSettings},
+            DefaultBackend,
+        },
+        AudioManager, AudioManagerSettings,
+    },
+    sound::{
+        static_sound::{StaticSoundData, StaticSoundHandle},
+        streaming::{StreamingSoundData, StreamingSoundHandle},
+        FromFileError, PlaybackRate, SoundData,
+    },
+    track::{TrackBuilder, TrackHandle, TrackRoutes},
+};
+use serde::{Deserialize, Serialize};
+use spitfire_glow::console_log;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Cursor,
+    sync::Arc,
+};
+use vek::Vec2;
+
+pub type StreamHandle = StreamingSoundHandle<FromFileError>;
+
+pub const MASTER_BUS: &str = "master";
+pub const MUSIC_BUS: &str = "music";
+pub const SFX_BUS: &str = "sfx";
+pub const UI_BUS: &str = "ui";
+pub const VOICE_BUS: &str = "voice";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioBusConfig {
+    pub volume: f64,
+    pub muted: bool,
+}
+
+impl Default for AudioBusConfig {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    pub buses: BTreeMap<String, AudioBusConfig>,
+}
+
+/// Frames per second simulated by the null backend to advance playback state.
+const NULL_SAMPLE_RATE: u32 = 100;
+
+pub enum AudioBackend {
+    Device(Box<AudioManager<DefaultBackend>>),
+    Null {
+        manager: Box<AudioManager<MockBackend>>,
+        accumulator: f32,
+    },
+}
+
+impl Default for AudioBackend {
+    fn default() -> Self {
+        match AudioManager::<DefaultBackend>::new(AudioManagerSettings::default()) {
+            Ok(manager) => Self::Device(Box::new(manager)),
+            Err(error) => {
+                console_log!("* Audio device unavailable, using null backend: {}", error);
+                Self::null()
+            }
+        }
+    }
+}
+
+impl AudioBackend {
+    pub fn null() -> Self {
+        Self::Null {
+            manager: Box::new(
+                AudioManager::new(AudioManagerSettings {
+                    backend_settings: MockBackendSettings {
+                        sample_rate: NULL_SAMPLE_RATE,
+                    },
+                    ..Default::default()
+                })
+                .unwrap(),
+            ),
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null { .. })
+    }
+
+    pub fn play<D: SoundData>(&mut self, data: D) -> Option<D::Handle> {
+        match self {
+            Self::Device(manager) => manager.play(data).ok(),
+            Self::Null { manager, .. } => manager.play(data).ok(),
+        }
+    }
+
+    pub fn add_sub_track(&mut self, builder: TrackBuilder) -> Option<TrackHandle> {
+        match self {
+            Self::Device(manager) => manager.add_sub_track(builder).ok(),
+            Self::Null { manager, .. } => manager.add_sub_track(builder).ok(),
+        }
+    }
+
+    pub fn maintain(&mut self, delta_time: f32) {
+        if let Self::Null {
+            manager,
+            accumulator,
+        } = self
+        {
+            *accumulator += delta_time * NULL_SAMPLE_RATE as f32;
+            let frames = accumulator.floor();
+            *accumulator -= frames;
+            let backend = manager.backend_mut();
+            backend.on_start_processing();
+            for _ in 0..frames as usize {
+                let _ = backend.process();
+            }
+            // Sounds publish position and state to their handles only when processing
+            // starts, so handles reflect playback done in this step.
+            backend.on_start_processing();
+        }
+    }
+}
+
+pub struct AudioBus {
+    pub track: TrackHandle,
+    volume: f64,
+    muted: bool,
+}
+
+impl AudioBus {
+    pub fn new(track: TrackHandle) -> Self {
+        Self {
+            track,
+            volume: 1.0,
+            muted: false,
+        }
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, value: f64) {
+        self.volume = value.max(0.0);
+        self.apply();
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn set_muted(&mut self, value: bool) {
+        self.muted = value;
+        self.apply();
+    }
+
+    pub fn config(&self) -> AudioBusConfig {
+        AudioBusConfig {
+            volume: self.volume,
+            muted: self.muted,
+        }
+    }
+
+    pub fn apply_config(&mut self, config: &AudioBusConfig) {
+        self.volume = config.volume.max(0.0);
+        self.muted = config.muted;
+        self.apply();
+    }
+
+    fn apply(&mut self) {
+        let volume = if self.muted { 0.0 } else { self.volume };
+        self.track.set_volume(volume, Default::default());
+    }
+}
+
+pub struct Audio {
+    pub manager: AudioBackend,
+    pub sounds: HashMap<String, StaticSoundData>,
+    pub streams: HashMap<String, Arc<[u8]>>,
+    pub groups: HashMap<String, SoundGroup>,
+    pub listener: AudioListener,
+    pub music: MusicDirector,
+    buses: HashMap<String, AudioBus>,
+    emitters: BTreeMap<AudioEmitterId, AudioEmitter>,
+    emitters_id_generator: u64,
+    groups_state: HashMap<String, SoundGroupState>,
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self::new(AudioBackend::default())
+    }
+}
+
+impl Audio {
+    pub fn new(manager: AudioBackend) -> Self {
+        let mut result = Self {
+            manager,
+            sounds: Default::default(),
+            streams: Default::default(),
+            groups: Default::default(),
+            listener: Default::default(),
+            music: Default::default(),
+            buses: Default::default(),
+            emitters: Default::default(),
+            emitters_id_generator: 0,
+            groups_state: Default::default(),
+        };
+        result.add_bus(MASTER_BUS, TrackBuilder::new());
+        for name in [MUSIC_BUS, SFX_BUS, UI_BUS, VOICE_BUS] {
+            result.add_bus(name, TrackBuilder::new());
+        }
+        result
+    }
+
+    pub fn null() -> Self {
+        Self::new(AudioBackend::null())
+    }
+
+    pub fn maintain(&mut self, delta_time: f32) {
+        self.manager.maintain(delta_time);
+        let mut music = std::mem::take(&mut self.music);
+        music.maintain(self, delta_time);
+        self.music = music;
+        self.emitters.retain(|_, emitter| emitter.is_playing());
+        for emitter in self.emitters.values_mut() {
+            emitter.update(&self.listener);
+        }
+        for state in self.groups_state.values_mut() {
+            state.cooldown = (state.cooldown - delta_time).max(0.0);
+            state
+                .voices
+                .retain(|voice| self.emitters.contains_key(voice));
+        }
+    }
+
+    pub fn add_bus(&mut self, name: impl ToString, builder: TrackBuilder) -> Option<&mut AudioBus> {
+        let name = name.to_string();
+        let builder = match self.buses.get(MASTER_BUS) {
+            Some(master) if name != MASTER_BUS => {
+                builder.routes(TrackRoutes::parent(master.track.id()))
+            }
+            _ => builder,
+        };
+        let track = self.manager.add_sub_track(builder)?;
+        self.buses.insert(name.clone(), AudioBus::new(track));
+        self.buses.get_mut(&name)
+    }
+
+    pub fn remove_bus(&mut self, name: &str) -> Option<AudioBus> {
+        self.buses.remove(name)
+    }
+
+    pub fn bus(&self, name: &str) -> Option<&AudioBus> {
+        self.buses.get(name)
+    }
+
+    pub fn bus_mut(&mut self, name: &str) -> Option<&mut AudioBus> {
+        self.buses.get_mut(name)
+    }
+
+    pub fn buses(&self) -> impl Iterator<Item = (&str, &AudioBus)> {
+        self.buses.iter().map(|(name, bus)| (name.as_str(), bus))
+    }
+
+    pub fn set_bus_volume(&mut self, name: &str, value: f64) {
+        if let Some(bus) = self.buses.get_mut(name) {
+            bus.set_volume(value);
+        }
+    }
+
+    pub fn set_bus_muted(&mut self, name: &str, value: bool) {
+        if let Some(bus) = self.buses.get_mut(name) {
+            bus.set_muted(value);
+        }
+    }
+
+    pub fn config(&self) -> AudioConfig {
+        AudioConfig {
+            buses: self
+                .buses
+                .iter()
+                .map(|(name, bus)| (name.to_owned(), bus.config()))
+                .collect(),
+        }
+    }
+
+    pub fn apply_config(&mut self, config: &AudioConfig) {
+        for (name, config) in &config.buses {
+            if let Some(bus) = self.buses.get_mut(name) {
+                bus.apply_config(config);
+            }
+        }
+    }
+
+    pub fn play(&mut self, id: &str) -> Option<StaticSoundHandle> {
+        self.play_on(id, SFX_BUS)
+    }
+
+    pub fn play_on(&mut self, id: &str, bus: &str) -> Option<StaticSoundHandle> {
+        let mut data = self.sounds.get(id)?.clone();
+        if let Some(bus) = self.buses.get(bus) {
+            data = data.output_destination(&bus.track);
+        }
+        self.manager.play(data)
+    }
+
+    /// Plays sound or sound group variation positioned in world space.
+    pub fn play_at(&mut self, id: &str, position: Vec2<f32>) -> Option<AudioEmitterId> {
+        if self.groups.contains_key(id) {
+            self.play_group_emitter(id, Some(position))
+        } else {
+            self.play_at_on(id, SFX_BUS, position)
+        }
+    }
+
+    pub fn play_at_on(
+        &mut self,
+        id: &str,
+        bus: &str,
+        position: Vec2<f32>,
+    ) -> Option<AudioEmitterId> {
+        let data = self.sounds.get(id)?.clone();
+        self.spawn_emitter(data, bus, Some(position), 1.0)
+    }
+
+    /// Plays random variation of sound group, respecting its voices limit and cooldown.
+    pub fn play_group(&mut self, id: &str) -> Option<AudioEmitterId> {
+        self.play_group_emitter(id, None)
+    }
+
+    fn play_group_emitter(
+        &mut self,
+        id: &str,
+        position: Option<Vec2<f32>>,
+    ) -> Option<AudioEmitterId> {
+        let group = self.groups.get(id)?;
+        let state = self.groups_state.entry(id.to_owned()).or_default();
+        state
+            .voices
+            .retain(|voice| self.emitters.contains_key(voice));
+        if state.cooldown > 0.0 || (group.max_voices > 0 && state.voices.len() >= group.max_voices)
+        {
+            return None;
+        }
+        let variations = group.variations(&self.sounds);
+        let name = variations[state.pick_variation(variations.len())?];
+        let data = self
+            .sounds
+            .get(name)?
+            .clone()
+            .playback_rate(PlaybackRate::Factor(group.random_pitch()));
+        let volume = group.random_volume() as f32;
+        let bus = group.bus.clone().unwrap_or_else(|| SFX_BUS.to_owned());
+        state.cooldown = group.cooldown;
+        let result = self.spawn_emitter(data, &bus, position, volume)?;
+        if let Some(state) = self.groups_state.get_mut(id) {
+            state.voices.push(result);
+        }
+        Some(result)
+    }
+
+    fn spawn_emitter(
+        &mut self,
+        mut data: StaticSoundData,
+        bus: &str,
+        position: Option<Vec2<f32>>,
+        volume: f32,
+    ) -> Option<AudioEmitterId> {
+        if let Some(bus) = self.buses.get(bus) {
+            data = data.output_destination(&bus.track);
+        }
+        let mut emitter = AudioEmitter::new(
+            self.manager.play(data)?,
+            position.unwrap_or(self.listener.position),
+        );
+        emitter.volume = volume;
+        emitter.spatial = position.is_some();
+        emitter.update(&self.listener);
+        let result = AudioEmitterId(self.emitters_id_generator);
+        self.emitters_id_generator = self.emitters_id_generator.wrapping_add(1);
+        self.emitters.insert(result, emitter);
+        Some(result)
+    }
+
+    pub fn emitter(&self, id: AudioEmitterId) -> Option<&AudioEmitter> {
+        self.emitters.get(&id)
+    }
+
+    pub fn emitter_mut(&mut self, id: AudioEmitterId) -> Option<&mut AudioEmitter> {
+        self.emitters.get_mut(&id)
+    }
+
+    pub fn emitters(&self) -> impl Iterator<Item = (AudioEmitterId, &AudioEmitter)> {
+        self.emitters.iter().map(|(id, emitter)| (*id, emitter))
+    }
+
+    pub fn set_emitter_position(&mut self, id: AudioEmitterId, position: Vec2<f32>) {
+        if let Some(emitter) = self.emitters.get_mut(&id) {
+            emitter.position = position;
+        }
+    }
+
+    pub fn update_occlusion(&mut self, world: &GridWorld) {
+        for emitter in self.emitters.values_mut() {
+            emitter.update_occlusion(&self.listener, world);
+        }
+    }
+
+    pub fn stream_data(&self, id: &str) -> Option<StreamingSoundData<FromFileError>> {
+        StreamingSoundData::from_cursor(Cursor::new(self.streams.get(id)?.clone())).ok()
+    }
+
+    pub fn play_stream(&mut self, id: &str) -> Option<StreamHandle> {
+        self.play_stream_on(id, MUSIC_BUS)
+    }
+
+    pub fn play_stream_on(&mut self, id: &str, bus: &str) -> Option<StreamHandle> {
+        let mut data = self.stream_data(id)?;
+        if let Some(bus) = self.buses.get(bus) {
+            data = data.output_destination(&bus.track);
+        }
+        self.manager.play(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kira::{sound::PlaybackState, tween::Tween, Frame};
+
+    #[test]
+    fn test_null_audio() {
+        let mut audio = Audio::null();
+        assert!(audio.manager.is_null());
+        audio.set_bus_volume(MUSIC_BUS, 0.5);
+        audio.set_bus_muted(SFX_BUS, true);
+        let config = audio.config();
+        assert_eq!(config.buses[MUSIC_BUS].volume, 0.5);
+        assert!(config.buses[SFX_BUS].muted);
+        assert!(audio.play("missing").is_none());
+        audio.maintain(0.1);
+
+        audio.sounds.insert(
+            "beep".to_owned(),
+            StaticSoundData {
+                sample_rate: NULL_SAMPLE_RATE,
+                frames: vec![Frame::ZERO; NULL_SAMPLE_RATE as usize].into(),
+                settings: Default::default(),
+                slice: None,
+            },
+        );
+        let mut handle = audio.play("beep").unwrap();
+        assert_eq!(handle.state(), PlaybackState::Playing);
+        audio.maintain(0.5);
+        assert!(handle.position() > 0.3 && handle.position() < 0.6);
+        handle.pause(Tween::default());
+        audio.maintain(0.1);
+        assert_eq!(handle.state(), PlaybackState::Paused);
+        let position = handle.position();
+        audio.maintain(0.5);
+        assert_eq!(handle.position(), position);
+        handle.resume(Tween::default());
+        audio.maintain(1.0);
+        assert_eq!(handle.state(), PlaybackState::Stopped);
+    }
+}
@@ -0,0 +1,421 @@
+use crate::audio::{Audio, StreamHandle, MUSIC_BUS};
+use kira::{sound::PlaybackState, tween::Tween};
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MusicSync {
+    #[default]
+    Immediate,
+    Beat,
+    Bar,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MusicLayer {
+    pub stream: String,
+    pub volume: f64,
+    /// Intensity at which layer plays at full volume.
+    pub intensity: f32,
+    /// Intensity distance over which layer fades out, zero or less means always audible.
+    pub spread: f32,
+}
+
+impl Default for MusicLayer {
+    fn default() -> Self {
+        Self {
+            stream: Default::default(),
+            volume: 1.0,
+            intensity: 0.0,
+            spread: 0.0,
+        }
+    }
+}
+
+impl MusicLayer {
+    pub fn new(stream: impl ToString) -> Self {
+        Self {
+            stream: stream.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_volume(mut self, value: f64) -> Self {
+        self.volume = value;
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f32, spread: f32) -> Self {
+        self.intensity = intensity;
+        self.spread = spread;
+        self
+    }
+
+    pub fn weight(&self, intensity: f32) -> f32 {
+        if self.spread <= 0.0 {
+            1.0
+        } else {
+            (1.0 - (intensity - self.intensity).abs() / self.spread).clamp(0.0, 1.0)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MusicCue {
+    pub layers: Vec<MusicLayer>,
+    /// Beats per minute.
+    pub tempo: f64,
+    pub beats_per_bar: u32,
+    /// Point at which this cue can be left for another one.
+    pub sync: MusicSync,
+    pub fade_in: f32,
+    pub fade_out: f32,
+    pub looping: bool,
+}
+
+impl Default for MusicCue {
+    fn default() -> Self {
+        Self {
+            layers: Default::default(),
+            tempo: 120.0,
+            beats_per_bar: 4,
+            sync: MusicSync::Immediate,
+            fade_in: 1.0,
+            fade_out: 1.0,
+            looping: true,
+        }
+    }
+}
+
+impl MusicCue {
+    pub fn new(layer: MusicLayer) -> Self {
+        Self {
+            layers: vec![layer],
+            ..Default::default()
+        }
+    }
+
+    pub fn with_layer(mut self, layer: MusicLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    pub fn with_tempo(mut self, tempo: f64, beats_per_bar: u32) -> Self {
+        self.tempo = tempo;
+        self.beats_per_bar = beats_per_bar;
+        self
+    }
+
+    pub fn with_sync(mut self, value: MusicSync) -> Self {
+        self.sync = value;
+        self
+    }
+
+    pub fn with_fade(mut self, fade_in: f32, fade_out: f32) -> Self {
+        self.fade_in = fade_in;
+        self.fade_out = fade_out;
+        self
+    }
+
+    pub fn with_looping(mut self, value: bool) -> Self {
+        self.looping = value;
+        self
+    }
+
+    /// Seconds left from given playback position to next sync point.
+    pub fn time_to_sync(&self, position: f64) -> f64 {
+        let beat = 60.0 / self.tempo.max(f64::EPSILON);
+        let period = match self.sync {
+            MusicSync::Immediate => return 0.0,
+            MusicSync::Beat => beat,
+            MusicSync::Bar => beat * self.beats_per_bar.max(1) as f64,
+        };
+        let result = period - position.rem_euclid(period);
+        if result >= period - f64::EPSILON {
+            0.0
+        } else {
+            result
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MusicPlaylist {
+    pub cues: Vec<String>,
+    pub shuffle: bool,
+}
+
+impl MusicPlaylist {
+    pub fn new(cue: impl ToString) -> Self {
+        Self {
+            cues: vec![cue.to_string()],
+            shuffle: false,
+        }
+    }
+
+    pub fn with_cue(mut self, cue: impl ToString) -> Self {
+        self.cues.push(cue.to_string());
+        self
+    }
+
+    pub fn with_shuffle(mut self, value: bool) -> Self {
+        self.shuffle = value;
+        self
+    }
+}
+
+struct MusicCueInstance {
+    cue: String,
+    layers: Vec<(MusicLayer, StreamHandle)>,
+    fade: f32,
+    fade_speed: f32,
+}
+
+impl MusicCueInstance {
+    fn position(&self) -> f64 {
+        self.layers
+            .first()
+            .map(|(_, handle)| handle.position())
+            .unwrap_or_default()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.layers
+            .iter()
+            .all(|(_, handle)| handle.state() == PlaybackState::Stopped)
+    }
+
+    fn update(&mut self, delta_time: f32, intensity: f32) {
+        self.fade = (self.fade + self.fade_speed * delta_time).clamp(0.0, 1.0);
+        for (layer, handle) in &mut self.layers {
+            let volume = layer.volume * (self.fade * layer.weight(intensity)) as f64;
+            handle.set_volume(volume, Tween::default());
+        }
+    }
+
+    fn stop(&mut self) {
+        for (_, handle) in &mut self.layers {
+            handle.stop(Tween::default());
+        }
+    }
+}
+
+/// Plays music cues for target state, crossfading between them at cue sync points.
+pub struct MusicDirector {
+    pub cues: HashMap<String, MusicCue>,
+    pub playlists: HashMap<String, MusicPlaylist>,
+    pub bus: String,
+    /// Speed at which current intensity approaches target intensity, per second.
+    pub intensity_speed: f32,
+    state: Option<String>,
+    playlist_index: usize,
+    intensity: f32,
+    target_intensity: f32,
+    active: Option<MusicCueInstance>,
+    fading: Vec<MusicCueInstance>,
+    pending: bool,
+}
+
+impl Default for MusicDirector {
+    fn default() -> Self {
+        Self {
+            cues: Default::default(),
+            playlists: Default::default(),
+            bus: MUSIC_BUS.to_owned(),
+            intensity_speed: 1.0,
+            state: None,
+            playlist_index: 0,
+            intensity: 0.0,
+            target_intensity: 0.0,
+            active: None,
+            fading: Default::default(),
+            pending: false,
+        }
+    }
+}
+
+impl MusicDirector {
+    pub fn with_cue(mut self, name: impl ToString, cue: MusicCue) -> Self {
+        self.cues.insert(name.to_string(), cue);
+        self
+    }
+
+    pub fn with_playlist(mut self, state: impl ToString, playlist: MusicPlaylist) -> Self {
+        self.playlists.insert(state.to_string(), playlist);
+        self
+    }
+
+    pub fn with_bus(mut self, value: impl ToString) -> Self {
+        self.bus = value.to_string();
+        self
+    }
+
+    pub fn state(&self) -> Option<&str> {
+        self.state.as_deref()
+    }
+
+    /// Requests switch to playlist of given state, applied at next sync point of current cue.
+    pub fn set_state(&mut self, state: impl ToString) {
+        let state = state.to_string();
+        if self.state.as_ref() != Some(&state) {
+            self.state = Some(state);
+            self.playlist_index = 0;
+            self.pending = true;
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.state = None;
+        self.pending = true;
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn set_intensity(&mut self, value: f32) {
+        self.target_intensity = value;
+    }
+
+    pub fn current_cue(&self) -> Option<&str> {
+        self.active.as_ref().map(|instance| instance.cue.as_str())
+    }
+
+    pub fn maintain(&mut self, audio: &mut Audio, delta_time: f32) {
+        let step = self.intensity_speed * delta_time;
+        self.intensity += (self.target_intensity - self.intensity).clamp(-step, step);
+
+        if let Some(active) = self.active.as_ref() {
+            if active.is_finished() {
+                self.active = None;
+                self.playlist_index += 1;
+                self.pending = true;
+            }
+        }
+
+        if self.pending {
+            let ready = self
+                .active
+                .as_ref()
+                .and_then(|active| {
+                    let cue = self.cues.get(&active.cue)?;
+                    Some(cue.time_to_sync(active.position()) <= delta_time as f64)
+                })
+                .unwrap_or(true);
+            if ready {
+                self.pending = false;
+                self.switch(audio);
+            }
+        }
+
+        if let Some(active) = self.active.as_mut() {
+            active.update(delta_time, self.intensity);
+        }
+        for instance in &mut self.fading {
+            instance.update(delta_time, self.intensity);
+            if instance.fade <= 0.0 {
+                instance.stop();
+            }
+        }
+        self.fading.retain(|instance| instance.fade > 0.0);
+    }
+
+    fn switch(&mut self, audio: &mut Audio) {
+        let next = self.next_cue();
+        if next.is_some() && next.as_deref() == self.current_cue() {
+            return;
+        }
+        if let Some(mut active) = self.active.take() {
+            let fade_out = self
+                .cues
+                .get(&active.cue)
+                .map(|cue| cue.fade_out)
+                .unwrap_or_default();
+            active.fade_speed = -1.0 / fade_out.max(f32::EPSILON);
+            self.fading.push(active);
+        }
+        let Some(name) = next else {
+            return;
+        };
+        let Some(cue) = self.cues.get(&name) else {
+            return;
+        };
+        let layers = cue
+            .layers
+            .iter()
+            .filter_map(|layer| {
+                let mut handle = audio.play_stream_on(&layer.stream, &self.bus)?;
+                handle.set_volume(0.0, Tween::default());
+                if cue.looping {
+                    handle.set_loop_region(..);
+                }
+                Some((layer.clone(), handle))
+            })
+            .collect::<Vec<_>>();
+        if layers.is_empty() {
+            // Streams are not loaded yet, so try again on next maintenance.
+            self.pending = true;
+            return;
+        }
+        self.active = Some(MusicCueInstance {
+            cue: name,
+            layers,
+            fade: 0.0,
+            fade_speed: 1.0 / cue.fade_in.max(f32::EPSILON),
+        });
+    }
+
+    fn next_cue(&mut self) -> Option<String> {
+        let playlist = self.playlists.get(self.state.as_ref()?)?;
+        if playlist.cues.is_empty() {
+            return None;
+        }
+        if playlist.shuffle && self.playlist_index > 0 {
+            self.playlist_index = thread_rng().gen_range(0..playlist.cues.len());
+        }
+        Some(playlist.cues[self.playlist_index % playlist.cues.len()].to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::sfx::SfxParams;
+
+    #[test]
+    fn test_music_director() {
+        let cue = MusicCue::default()
+            .with_tempo(120.0, 4)
+            .with_sync(MusicSync::Beat);
+        assert_eq!(cue.time_to_sync(0.0), 0.0);
+        assert_eq!(cue.time_to_sync(0.25), 0.25);
+        let cue = cue.with_sync(MusicSync::Bar);
+        assert_eq!(cue.time_to_sync(1.5), 0.5);
+        assert_eq!(cue.with_sync(MusicSync::Immediate).time_to_sync(1.5), 0.0);
+
+        let layer = MusicLayer::new("battle").with_intensity(1.0, 0.5);
+        assert_eq!(layer.weight(1.0), 1.0);
+        assert_eq!(layer.weight(0.75), 0.5);
+        assert_eq!(layer.weight(0.0), 0.0);
+        assert_eq!(MusicLayer::new("base").weight(0.0), 1.0);
+
+        let mut audio = Audio::null();
+        let mut director = MusicDirector::default()
+            .with_cue("explore", MusicCue::new(MusicLayer::new("forest")))
+            .with_playlist("explore", MusicPlaylist::new("explore"));
+        director.set_state("explore");
+        director.maintain(&mut audio, 0.1);
+        assert_eq!(director.state(), Some("explore"));
+        assert_eq!(director.current_cue(), None);
+
+        audio
+            .streams
+            .insert("forest".to_owned(), SfxParams::default().to_wav(0).into());
+        director.maintain(&mut audio, 0.1);
+        assert_eq!(director.current_cue(), Some("explore"));
+    }
+}
@@ -0,0 +1,367 @@
+use kira::sound::{static_sound::StaticSoundData, FromFileError};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{f64::consts::TAU, io::Cursor};
+
+pub const SFX_SAMPLE_RATE: u32 = 44100;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SfxWaveform {
+    #[default]
+    Square,
+    Sawtooth,
+    Sine,
+    Triangle,
+    Noise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SfxPreset {
+    Pickup,
+    Laser,
+    Explosion,
+    Hit,
+    Jump,
+    PowerUp,
+    Blip,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SfxParams {
+    pub waveform: SfxWaveform,
+    pub volume: f64,
+    /// Envelope stage durations in seconds.
+    pub attack: f64,
+    pub sustain: f64,
+    pub decay: f64,
+    /// Extra volume at sustain start, fading out over sustain.
+    pub sustain_punch: f64,
+    /// Start frequency in Hz.
+    pub frequency: f64,
+    /// Frequency in Hz below which sound gets cut, zero disables cut.
+    pub min_frequency: f64,
+    /// Frequency slide in octaves per second.
+    pub frequency_slide: f64,
+    /// Change of frequency slide in octaves per second squared.
+    pub frequency_delta_slide: f64,
+    /// Vibrato depth as fraction of frequency.
+    pub vibrato_depth: f64,
+    /// Vibrato speed in Hz.
+    pub vibrato_speed: f64,
+    /// Frequency multiplier applied after arpeggio time.
+    pub arpeggio_multiplier: f64,
+    /// Seconds after which arpeggio multiplier applies, zero disables arpeggio.
+    pub arpeggio_time: f64,
+    /// Square wave duty cycle.
+    pub duty: f64,
+    /// Duty cycle change per second.
+    pub duty_sweep: f64,
+}
+
+impl Default for SfxParams {
+    fn default() -> Self {
+        Self {
+            waveform: SfxWaveform::Square,
+            volume: 0.5,
+            attack: 0.0,
+            sustain: 0.1,
+            decay: 0.2,
+            sustain_punch: 0.0,
+            frequency: 440.0,
+            min_frequency: 0.0,
+            frequency_slide: 0.0,
+            frequency_delta_slide: 0.0,
+            vibrato_depth: 0.0,
+            vibrato_speed: 0.0,
+            arpeggio_multiplier: 1.0,
+            arpeggio_time: 0.0,
+            duty: 0.5,
+            duty_sweep: 0.0,
+        }
+    }
+}
+
+impl SfxParams {
+    pub fn preset(preset: SfxPreset, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        match preset {
+            SfxPreset::Pickup => Self {
+                waveform: SfxWaveform::Square,
+                sustain: rng.gen_range(0.02..0.08),
+                decay: rng.gen_range(0.1..0.3),
+                sustain_punch: rng.gen_range(0.3..0.6),
+                frequency: rng.gen_range(800.0..1600.0),
+                arpeggio_multiplier: rng.gen_range(1.3..1.6),
+                arpeggio_time: rng.gen_range(0.03..0.08),
+                ..Default::default()
+            },
+            SfxPreset::Laser => Self {
+                waveform: match rng.gen_range(0..3) {
+                    0 => SfxWaveform::Square,
+                    1 => SfxWaveform::Sawtooth,
+                    _ => SfxWaveform::Sine,
+                },
+                sustain: rng.gen_range(0.05..0.15),
+                decay: rng.gen_range(0.05..0.2),
+                frequency: rng.gen_range(1000.0..2500.0),
+                min_frequency: rng.gen_range(100.0..300.0),
+                frequency_slide: rng.gen_range(-12.0..-4.0),
+                duty: rng.gen_range(0.2..0.5),
+                duty_sweep: rng.gen_range(-0.5..0.5),
+                ..Default::default()
+            },
+            SfxPreset::Explosion => Self {
+                waveform: SfxWaveform::Noise,
+                sustain: rng.gen_range(0.1..0.3),
+                decay: rng.gen_range(0.3..0.8),
+                sustain_punch: rng.gen_range(0.2..0.8),
+                frequency: rng.gen_range(100.0..500.0),
+                frequency_slide: rng.gen_range(-2.0..0.5),
+                vibrato_depth: rng.gen_range(0.0..0.3),
+                vibrato_speed: rng.gen_range(5.0..20.0),
+                ..Default::default()
+            },
+            SfxPreset::Hit => Self {
+                waveform: match rng.gen_range(0..3) {
+                    0 => SfxWaveform::Square,
+                    1 => SfxWaveform::Sawtooth,
+                    _ => SfxWaveform::Noise,
+                },
+                sustain: rng.gen_range(0.01..0.05),
+                decay: rng.gen_range(0.05..0.2),
+                frequency: rng.gen_range(200.0..800.0),
+                frequency_slide: rng.gen_range(-6.0..-2.0),
+                duty: rng.gen_range(0.2..0.5),
+                ..Default::default()
+            },
+            SfxPreset::Jump => Self {
+                waveform: SfxWaveform::Square,
+                sustain: rng.gen_range(0.05..0.15),
+                decay: rng.gen_range(0.1..0.25),
+                frequency: rng.gen_range(250.0..600.0),
+                frequency_slide: rng.gen_range(2.0..6.0),
+                duty: rng.gen_range(0.2..0.5),
+                ..Default::default()
+            },
+            SfxPreset::PowerUp => Self {
+                waveform: match rng.gen_range(0..2) {
+                    0 => SfxWaveform::Square,
+                    _ => SfxWaveform::Sawtooth,
+                },
+                sustain: rng.gen_range(0.1..0.3),
+                decay: rng.gen_range(0.2..0.4),
+                frequency: rng.gen_range(200.0..500.0),
+                frequency_slide: rng.gen_range(1.0..4.0),
+                vibrato_depth: rng.gen_range(0.0..0.1),
+                vibrato_speed: rng.gen_range(10.0..30.0),
+                ..Default::default()
+            },
+            SfxPreset::Blip => Self {
+                waveform: match rng.gen_range(0..2) {
+                    0 => SfxWaveform::Square,
+                    _ => SfxWaveform::Sine,
+                },
+                sustain: rng.gen_range(0.03..0.08),
+                decay: rng.gen_range(0.01..0.05),
+                frequency: rng.gen_range(600.0..1400.0),
+                duty: rng.gen_range(0.2..0.5),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.attack.max(0.0) + self.sustain.max(0.0) + self.decay.max(0.0)
+    }
+
+    pub fn synthesize(&self, seed: u64) -> Vec<f32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let sample_rate = SFX_SAMPLE_RATE as f64;
+        let attack = self.attack.max(0.0);
+        let sustain = self.sustain.max(0.0);
+        let decay = self.decay.max(0.0);
+        let count = (self.duration() * sample_rate) as usize;
+        let mut result = Vec::with_capacity(count);
+        let mut frequency = self.frequency;
+        let mut slide = self.frequency_slide;
+        let mut phase = 0.0;
+        let mut noise = rng.gen_range(-1.0..=1.0);
+        for index in 0..count {
+            let time = index as f64 / sample_rate;
+            let mut current =
+                frequency * (1.0 + self.vibrato_depth * (TAU * self.vibrato_speed * time).sin());
+            if self.arpeggio_time > 0.0 && time >= self.arpeggio_time {
+                current *= self.arpeggio_multiplier;
+            }
+            if self.min_frequency > 0.0 && current < self.min_frequency {
+                break;
+            }
+            phase += current / sample_rate;
+            if phase >= 1.0 {
+                phase = phase.fract();
+                noise = rng.gen_range(-1.0..=1.0);
+            }
+            let duty = (self.duty + self.duty_sweep * time).clamp(0.0, 1.0);
+            let sample = match self.waveform {
+                SfxWaveform::Square => {
+                    if phase < duty {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                SfxWaveform::Sawtooth => 1.0 - phase * 2.0,
+                SfxWaveform::Sine => (TAU * phase).sin(),
+                SfxWaveform::Triangle => 1.0 - (phase * 4.0 - 2.0).abs(),
+                SfxWaveform::Noise => noise,
+            };
+            let envelope = if time < attack {
+                time / attack
+            } else if time < attack + sustain {
+                1.0 + self.sustain_punch * (1.0 - (time - attack) / sustain)
+            } else {
+                1.0 - (time - attack - sustain) / decay.max(f64::EPSILON)
+            };
+            result.push((sample * envelope * self.volume).clamp(-1.0, 1.0) as f32);
+            slide += self.frequency_delta_slide / sample_rate;
+            frequency *= 2.0f64.powf(slide / sample_rate);
+        }
+        result
+    }
+
+    /// Encodes synthesized samples as mono 16-bit PCM WAV file.
+    pub fn to_wav(&self, seed: u64) -> Vec<u8> {
+        let samples = self.synthesize(seed);
+        let data_size = samples.len() as u32 * 2;
+        let mut result = Vec::with_capacity(44 + data_size as usize);
+        result.extend_from_slice(b"RIFF");
+        result.extend_from_slice(&(36 + data_size).to_le_bytes());
+        result.extend_from_slice(b"WAVEfmt ");
+        result.extend_from_slice(&16u32.to_le_bytes());
+        result.extend_from_slice(&1u16.to_le_bytes());
+        result.extend_from_slice(&1u16.to_le_bytes());
+        result.extend_from_slice(&SFX_SAMPLE_RATE.to_le_bytes());
+        result.extend_from_slice(&(SFX_SAMPLE_RATE * 2).to_le_bytes());
+        result.extend_from_slice(&2u16.to_le_bytes());
+        result.extend_from_slice(&16u16.to_le_bytes());
+        result.extend_from_slice(b"data");
+        result.extend_from_slice(&data_size.to_le_bytes());
+        for sample in samples {
+            result.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes());
+        }
+        result
+    }
+
+    pub fn sound_data(&self, seed: u64) -> Result<StaticSoundData, FromFileError> {
+        StaticSoundData::from_cursor(Cursor::new(self.to_wav(seed)))
+    }
+}
+
+/// Sound effect description, where explicit params are applied on top of preset.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SfxDescription {
+    pub preset: Option<SfxPreset>,
+    pub seed: u64,
+    /// Explicitly set params fields, overriding ones of preset.
+    pub params: Option<toml::Table>,
+}
+
+impl SfxDescription {
+    pub fn load_from_str(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let result = toml::from_str::<Self>(content)?;
+        result.params()?;
+        Ok(result)
+    }
+
+    pub fn params(&self) -> Result<SfxParams, Box<dyn std::error::Error>> {
+        let params = self
+            .preset
+            .map(|preset| SfxParams::preset(preset, self.seed))
+            .unwrap_or_default();
+        let Some(overrides) = self.params.as_ref() else {
+            return Ok(params);
+        };
+        let mut table = toml::Table::try_from(params)?;
+        table.extend(overrides.clone());
+        Ok(table.try_into()?)
+    }
+
+    pub fn sound_data(&self) -> Result<StaticSoundData, Box<dyn std::error::Error>> {
+        Ok(self.params()?.sound_data(self.seed)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sfx() {
+        let params = SfxParams::preset(SfxPreset::Pickup, 42);
+        assert_eq!(params, SfxParams::preset(SfxPreset::Pickup, 42));
+        assert_ne!(params, SfxParams::preset(SfxPreset::Pickup, 7));
+        assert_eq!(params.synthesize(42), params.synthesize(42));
+
+        let samples = SfxParams::default().synthesize(0);
+        assert_eq!(
+            samples.len(),
+            (SfxParams::default().duration() * SFX_SAMPLE_RATE as f64) as usize
+        );
+        assert!(samples.iter().all(|sample| (-1.0..=1.0).contains(sample)));
+        let wav = SfxParams::default().to_wav(0);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(wav.len(), 44 + samples.len() * 2);
+
+        let description = SfxDescription::load_from_str(
+            r#"
+            preset = "explosion"
+            seed = 3
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            description.params().unwrap(),
+            SfxParams::preset(SfxPreset::Explosion, 3)
+        );
+        let description = SfxDescription::load_from_str(
+            r#"
+            [params]
+            waveform = "sine"
+            frequency = 220.0
+            "#,
+        )
+        .unwrap();
+        let params = description.params().unwrap();
+        assert_eq!(params.waveform, SfxWaveform::Sine);
+        assert_eq!(params.frequency, 220.0);
+
+        let description = SfxDescription::load_from_str(
+            r#"
+            preset = "laser"
+            seed = 5
+
+            [params]
+            frequency = 220.0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            description.params().unwrap(),
+            SfxParams {
+                frequency: 220.0,
+                ..SfxParams::preset(SfxPreset::Laser, 5)
+            }
+        );
+        assert!(SfxDescription::load_from_str(
+            r#"
+            [params]
+            frequency = "high"
+            "#,
+        )
+        .is_err());
+    }
+}
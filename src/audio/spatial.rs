@@ -0,0 +1,169 @@
+use crate::grid_world::GridWorld;
+use kira::{
+    sound::{static_sound::StaticSoundHandle, PlaybackState},
+    tween::Tween,
+};
+use serde::{Deserialize, Serialize};
+use vek::Vec2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioAttenuation {
+    /// Distance within which emitter plays at full volume.
+    pub min_distance: f32,
+    /// Distance at which emitter becomes silent.
+    pub max_distance: f32,
+    pub rolloff: f32,
+}
+
+impl Default for AudioAttenuation {
+    fn default() -> Self {
+        Self {
+            min_distance: 32.0,
+            max_distance: 512.0,
+            rolloff: 1.0,
+        }
+    }
+}
+
+impl AudioAttenuation {
+    pub fn new(min_distance: f32, max_distance: f32) -> Self {
+        Self {
+            min_distance,
+            max_distance,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_rolloff(mut self, value: f32) -> Self {
+        self.rolloff = value;
+        self
+    }
+
+    pub fn gain(&self, distance: f32) -> f32 {
+        if distance <= self.min_distance {
+            return 1.0;
+        }
+        if distance >= self.max_distance {
+            return 0.0;
+        }
+        let range = (self.max_distance - self.min_distance).max(f32::EPSILON);
+        (1.0 - (distance - self.min_distance) / range).powf(self.rolloff.max(0.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioListener {
+    pub position: Vec2<f32>,
+    /// Listener position gets synced with main camera every frame.
+    pub follow_main_camera: bool,
+    /// Horizontal distance at which sound gets fully panned to one side.
+    pub panning_distance: f32,
+    /// Volume multiplier applied to occluded emitters.
+    pub occlusion_volume: f32,
+}
+
+impl Default for AudioListener {
+    fn default() -> Self {
+        Self {
+            position: Default::default(),
+            follow_main_camera: true,
+            panning_distance: 256.0,
+            occlusion_volume: 0.35,
+        }
+    }
+}
+
+impl AudioListener {
+    pub fn panning(&self, position: Vec2<f32>) -> f32 {
+        let offset = (position.x - self.position.x) / self.panning_distance.max(f32::EPSILON);
+        0.5 + offset.clamp(-1.0, 1.0) * 0.5
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AudioEmitterId(pub(crate) u64);
+
+pub struct AudioEmitter {
+    pub handle: StaticSoundHandle,
+    pub position: Vec2<f32>,
+    pub volume: f32,
+    /// Enables attenuation and panning, otherwise emitter plays as regular sound.
+    pub spatial: bool,
+    pub attenuation: AudioAttenuation,
+    /// Enables occlusion tests against grid world colliders.
+    pub occlusion: bool,
+    occluded: bool,
+}
+
+impl AudioEmitter {
+    pub fn new(handle: StaticSoundHandle, position: Vec2<f32>) -> Self {
+        Self {
+            handle,
+            position,
+            volume: 1.0,
+            spatial: true,
+            attenuation: Default::default(),
+            occlusion: false,
+            occluded: false,
+        }
+    }
+
+    pub fn is_occluded(&self) -> bool {
+        self.occluded
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.handle.state() != PlaybackState::Stopped
+    }
+
+    pub fn spatial_volume(&self, listener: &AudioListener) -> f32 {
+        if !self.spatial {
+            return self.volume;
+        }
+        let gain = self
+            .attenuation
+            .gain(self.position.distance(listener.position));
+        let occlusion = if self.occluded {
+            listener.occlusion_volume
+        } else {
+            1.0
+        };
+        self.volume * gain * occlusion
+    }
+
+    pub fn update(&mut self, listener: &AudioListener) {
+        self.handle
+            .set_volume(self.spatial_volume(listener) as f64, Tween::default());
+        if self.spatial {
+            self.handle
+                .set_panning(listener.panning(self.position) as f64, Tween::default());
+        }
+    }
+
+    pub fn update_occlusion(&mut self, listener: &AudioListener, world: &GridWorld) {
+        self.occluded = self.occlusion && world.is_line_blocked(listener.position, self.position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spatial_audio() {
+        let attenuation = AudioAttenuation::new(10.0, 110.0);
+        assert_eq!(attenuation.gain(0.0), 1.0);
+        assert_eq!(attenuation.gain(60.0), 0.5);
+        assert_eq!(attenuation.gain(200.0), 0.0);
+        assert_eq!(attenuation.with_rolloff(2.0).gain(60.0), 0.25);
+
+        let listener = AudioListener {
+            panning_distance: 100.0,
+            ..Default::default()
+        };
+        assert_eq!(listener.panning(Vec2::new(0.0, 50.0)), 0.5);
+        assert_eq!(listener.panning(Vec2::new(-50.0, 0.0)), 0.25);
+        assert_eq!(listener.panning(Vec2::new(500.0, 0.0)), 1.0);
+    }
+}
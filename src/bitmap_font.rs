@@ -0,0 +1,641 @@
+use raui_immediate_widgets::core::{
+    containers::content_box, image_box, Color, ContentBoxItemLayout, ImageBoxImage,
+    ImageBoxMaterial, ImageBoxProps, Rect,
+};
+use spitfire_core::Triangle;
+use spitfire_draw::{
+    context::DrawContext,
+    utils::{Drawable, ShaderRef, TextureRef, Vertex},
+};
+use spitfire_glow::{
+    graphics::{Graphics, GraphicsBatch},
+    renderer::{GlowBlending, GlowTextureFiltering, GlowUniformValue},
+};
+use std::{borrow::Cow, collections::HashMap, error::Error, sync::Arc};
+use vek::{Rgba, Vec2};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BmFontGlyph {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub offset: Vec2<i32>,
+    pub advance: i32,
+    pub page: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BmFontQuad {
+    pub page: u32,
+    pub position: Vec2<f32>,
+    pub size: Vec2<f32>,
+    /// Normalized texture coordinates of top-left and bottom-right corners.
+    pub uvs: [Vec2<f32>; 2],
+}
+
+/// AngelCode bitmap font descriptor, parsed from text, XML or binary `.fnt` files.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BmFont {
+    pub line_height: f32,
+    pub base: f32,
+    pub scale_size: Vec2<u32>,
+    /// Page texture files, relative to descriptor file.
+    pub pages: Vec<String>,
+    pub glyphs: HashMap<char, BmFontGlyph>,
+    pub kernings: HashMap<(char, char), i32>,
+}
+
+impl BmFont {
+    pub fn parse(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if bytes.starts_with(b"BMF") {
+            Self::parse_binary(bytes)
+        } else {
+            let content = std::str::from_utf8(bytes)?;
+            if content.trim_start().starts_with('<') {
+                Self::parse_xml(content)
+            } else {
+                Self::parse_text(content)
+            }
+        }
+    }
+
+    pub fn parse_text(content: &str) -> Result<Self, Box<dyn Error>> {
+        let mut result = Self::default();
+        for line in content.lines() {
+            let mut parts = split_text_line(line);
+            let Some(tag) = parts.next() else {
+                continue;
+            };
+            let attributes = parts
+                .filter_map(|part| part.split_once('='))
+                .map(|(key, value)| (key, value.trim_matches('"')))
+                .collect::<HashMap<_, _>>();
+            result.apply_element(tag, |name| attributes.get(name).copied())?;
+        }
+        Ok(result)
+    }
+
+    pub fn parse_xml(content: &str) -> Result<Self, Box<dyn Error>> {
+        let document = roxmltree::Document::parse(content)?;
+        let mut result = Self::default();
+        for node in document.descendants().filter(|node| node.is_element()) {
+            result.apply_element(node.tag_name().name(), |name| node.attribute(name))?;
+        }
+        Ok(result)
+    }
+
+    pub fn parse_binary(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if bytes.len() < 4 || bytes[3] != 3 {
+            return Err("Unsupported binary BMFont version".into());
+        }
+        let mut result = Self::default();
+        let mut cursor = 4;
+        while cursor + 5 <= bytes.len() {
+            let kind = bytes[cursor];
+            let size = read_u32(bytes, cursor + 1)? as usize;
+            let block = bytes
+                .get(cursor + 5..cursor + 5 + size)
+                .ok_or("Truncated binary BMFont block")?;
+            cursor += 5 + size;
+            match kind {
+                2 => {
+                    result.line_height = read_u16(block, 0)? as f32;
+                    result.base = read_u16(block, 2)? as f32;
+                    result.scale_size = Vec2::new(read_u16(block, 4)?, read_u16(block, 6)?)
+                        .map(|value| value as u32);
+                }
+                3 => {
+                    result.pages = block
+                        .split(|byte| *byte == 0)
+                        .filter(|name| !name.is_empty())
+                        .map(|name| String::from_utf8_lossy(name).into_owned())
+                        .collect();
+                }
+                4 => {
+                    for item in block.chunks_exact(20) {
+                        let Some(character) = char::from_u32(read_u32(item, 0)?) else {
+                            continue;
+                        };
+                        result.glyphs.insert(
+                            character,
+                            BmFontGlyph {
+                                x: read_u16(item, 4)? as u32,
+                                y: read_u16(item, 6)? as u32,
+                                width: read_u16(item, 8)? as u32,
+                                height: read_u16(item, 10)? as u32,
+                                offset: Vec2::new(
+                                    read_u16(item, 12)? as i16 as i32,
+                                    read_u16(item, 14)? as i16 as i32,
+                                ),
+                                advance: read_u16(item, 16)? as i16 as i32,
+                                page: item[18] as u32,
+                            },
+                        );
+                    }
+                }
+                5 => {
+                    for item in block.chunks_exact(10) {
+                        if let (Some(first), Some(second)) = (
+                            char::from_u32(read_u32(item, 0)?),
+                            char::from_u32(read_u32(item, 4)?),
+                        ) {
+                            result
+                                .kernings
+                                .insert((first, second), read_u16(item, 8)? as i16 as i32);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(result)
+    }
+
+    fn apply_element<'a>(
+        &mut self,
+        tag: &str,
+        attribute: impl Fn(&str) -> Option<&'a str>,
+    ) -> Result<(), Box<dyn Error>> {
+        let number = |name: &str| -> Result<i32, Box<dyn Error>> {
+            Ok(attribute(name)
+                .map(|value| value.parse::<i32>())
+                .transpose()
+                .map_err(|_| format!("Invalid BMFont `{}` attribute: `{}`", tag, name))?
+                .unwrap_or_default())
+        };
+        match tag {
+            "common" => {
+                self.line_height = number("lineHeight")? as f32;
+                self.base = number("base")? as f32;
+                self.scale_size = Vec2::new(number("scaleW")?, number("scaleH")?)
+                    .map(|value| value.max(0) as u32);
+            }
+            "page" => {
+                let id = number("id")?.max(0) as usize;
+                let file = attribute("file").ok_or("BMFont page is missing file")?;
+                if self.pages.len() <= id {
+                    self.pages.resize(id + 1, Default::default());
+                }
+                self.pages[id] = file.to_owned();
+            }
+            "char" => {
+                if let Some(character) = char::from_u32(number("id")? as u32) {
+                    self.glyphs.insert(
+                        character,
+                        BmFontGlyph {
+                            x: number("x")?.max(0) as u32,
+                            y: number("y")?.max(0) as u32,
+                            width: number("width")?.max(0) as u32,
+                            height: number("height")?.max(0) as u32,
+                            offset: Vec2::new(number("xoffset")?, number("yoffset")?),
+                            advance: number("xadvance")?,
+                            page: number("page")?.max(0) as u32,
+                        },
+                    );
+                }
+            }
+            "kerning" => {
+                if let (Some(first), Some(second)) = (
+                    char::from_u32(number("first")? as u32),
+                    char::from_u32(number("second")? as u32),
+                ) {
+                    self.kernings.insert((first, second), number("amount")?);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn kerning(&self, first: char, second: char) -> i32 {
+        self.kernings
+            .get(&(first, second))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Produces glyph quads in font units, with origin at top-left of first line.
+    pub fn layout(&self, text: &str) -> Vec<BmFontQuad> {
+        let scale_size = self.scale_size.map(|value| value.max(1) as f32);
+        let mut result = Vec::with_capacity(text.len());
+        let mut cursor = Vec2::<f32>::zero();
+        let mut previous = None;
+        for character in text.chars() {
+            if character == '\n' {
+                cursor.x = 0.0;
+                cursor.y += self.line_height;
+                previous = None;
+                continue;
+            }
+            let Some(glyph) = self.glyphs.get(&character) else {
+                continue;
+            };
+            if let Some(previous) = previous {
+                cursor.x += self.kerning(previous, character) as f32;
+            }
+            if glyph.width > 0 && glyph.height > 0 {
+                let min = Vec2::new(glyph.x, glyph.y).map(|value| value as f32);
+                let size = Vec2::new(glyph.width, glyph.height).map(|value| value as f32);
+                result.push(BmFontQuad {
+                    page: glyph.page,
+                    position: cursor + glyph.offset.map(|value| value as f32),
+                    size,
+                    uvs: [min / scale_size, (min + size) / scale_size],
+                });
+            }
+            cursor.x += glyph.advance as f32;
+            previous = Some(character);
+        }
+        result
+    }
+
+    pub fn measure(&self, text: &str) -> Vec2<f32> {
+        let mut result = Vec2::<f32>::zero();
+        let mut width = 0.0f32;
+        let mut lines = 1;
+        let mut previous = None;
+        for character in text.chars() {
+            if character == '\n' {
+                result.x = result.x.max(width);
+                width = 0.0;
+                lines += 1;
+                previous = None;
+                continue;
+            }
+            let Some(glyph) = self.glyphs.get(&character) else {
+                continue;
+            };
+            if let Some(previous) = previous {
+                width += self.kerning(previous, character) as f32;
+            }
+            width += glyph.advance as f32;
+            previous = Some(character);
+        }
+        result.x = result.x.max(width);
+        result.y = lines as f32 * self.line_height;
+        result
+    }
+}
+
+/// Bitmap font with names of its page textures.
+#[derive(Debug, Clone)]
+pub struct BmFontFace {
+    pub font: Arc<BmFont>,
+    /// Texture names of font pages.
+    pub textures: Vec<String>,
+    pub filtering: GlowTextureFiltering,
+}
+
+impl BmFontFace {
+    pub fn text(&self, text: impl ToString) -> BmFontText {
+        BmFontText::new(self.font.clone(), self.textures.clone())
+            .filtering(self.filtering)
+            .text(text)
+    }
+}
+
+/// Bitmap fonts by name, filled by bitmap font assets like `DrawContext::fonts`
+/// is filled by vector font assets.
+#[derive(Debug, Default, Clone)]
+pub struct BmFontMap {
+    fonts: HashMap<String, BmFontFace>,
+}
+
+impl BmFontMap {
+    pub fn insert(&mut self, name: impl ToString, face: BmFontFace) {
+        self.fonts.insert(name.to_string(), face);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<BmFontFace> {
+        self.fonts.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BmFontFace> {
+        self.fonts.get(name)
+    }
+
+    /// Text of font with given name, to draw in world or in GUI.
+    pub fn text(&self, name: &str, text: impl ToString) -> Option<BmFontText> {
+        Some(self.get(name)?.text(text))
+    }
+}
+
+/// Text drawable rendering bitmap font glyphs from font page textures.
+pub struct BmFontText {
+    pub shader: Option<ShaderRef>,
+    pub font: Arc<BmFont>,
+    /// Texture names of font pages.
+    pub textures: Vec<String>,
+    pub sampler: Cow<'static, str>,
+    pub filtering: GlowTextureFiltering,
+    pub text: String,
+    pub position: Vec2<f32>,
+    pub scale: f32,
+    pub pivot: Vec2<f32>,
+    pub tint: Rgba<f32>,
+    pub blending: GlowBlending,
+}
+
+impl BmFontText {
+    pub fn new(font: Arc<BmFont>, textures: Vec<String>) -> Self {
+        Self {
+            shader: None,
+            font,
+            textures,
+            sampler: "u_image".into(),
+            filtering: GlowTextureFiltering::Nearest,
+            text: Default::default(),
+            position: Default::default(),
+            scale: 1.0,
+            pivot: Default::default(),
+            tint: Rgba::white(),
+            blending: GlowBlending::Alpha,
+        }
+    }
+
+    pub fn shader(mut self, value: ShaderRef) -> Self {
+        self.shader = Some(value);
+        self
+    }
+
+    pub fn text(mut self, value: impl ToString) -> Self {
+        self.text = value.to_string();
+        self
+    }
+
+    pub fn position(mut self, value: Vec2<f32>) -> Self {
+        self.position = value;
+        self
+    }
+
+    pub fn scale(mut self, value: f32) -> Self {
+        self.scale = value;
+        self
+    }
+
+    pub fn pivot(mut self, value: Vec2<f32>) -> Self {
+        self.pivot = value;
+        self
+    }
+
+    pub fn tint(mut self, value: Rgba<f32>) -> Self {
+        self.tint = value;
+        self
+    }
+
+    pub fn filtering(mut self, value: GlowTextureFiltering) -> Self {
+        self.filtering = value;
+        self
+    }
+
+    pub fn size(&self) -> Vec2<f32> {
+        self.font.measure(&self.text) * self.scale
+    }
+}
+
+impl BmFontText {
+    /// Draws glyphs as GUI image widgets inside content box with given layout.
+    /// Scale is in GUI units per font unit, and pivot aligns text within the box.
+    pub fn draw_gui(&self, layout: ContentBoxItemLayout) {
+        let quads = self.font.layout(&self.text);
+        let origin = self.size() * self.pivot;
+        let tint = Color {
+            r: self.tint.r,
+            g: self.tint.g,
+            b: self.tint.b,
+            a: self.tint.a,
+        };
+        content_box(layout, || {
+            for quad in &quads {
+                let Some(texture) = self.textures.get(quad.page as usize) else {
+                    continue;
+                };
+                let min = quad.position * self.scale - origin;
+                let max = min + quad.size * self.scale;
+                let [uv_min, uv_max] = quad.uvs;
+                image_box((
+                    ContentBoxItemLayout {
+                        anchors: Rect {
+                            left: self.pivot.x,
+                            right: self.pivot.x,
+                            top: self.pivot.y,
+                            bottom: self.pivot.y,
+                        },
+                        margin: Rect {
+                            left: min.x,
+                            right: -max.x,
+                            top: min.y,
+                            bottom: -max.y,
+                        },
+                        ..Default::default()
+                    },
+                    ImageBoxProps {
+                        material: ImageBoxMaterial::Image(ImageBoxImage {
+                            id: texture.to_owned(),
+                            source_rect: Some(Rect {
+                                left: uv_min.x,
+                                right: uv_max.x,
+                                top: uv_min.y,
+                                bottom: uv_max.y,
+                            }),
+                            tint,
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    },
+                ));
+            }
+        });
+    }
+}
+
+impl Drawable for BmFontText {
+    fn draw(&self, context: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
+        let quads = self.font.layout(&self.text);
+        let origin = self.position - self.size() * self.pivot;
+        let color = self.tint.into_array();
+        let matrix = graphics.main_camera.world_matrix();
+        for (page, name) in self.textures.iter().enumerate() {
+            let Some(texture) = context.texture(Some(&TextureRef::name(name.clone()))) else {
+                continue;
+            };
+            let batch = GraphicsBatch {
+                shader: context.shader(self.shader.as_ref()),
+                uniforms: [
+                    (
+                        "u_projection_view".into(),
+                        GlowUniformValue::M4(matrix.into_col_array()),
+                    ),
+                    (self.sampler.clone(), GlowUniformValue::I1(0)),
+                ]
+                .into_iter()
+                .collect(),
+                textures: vec![(texture, self.filtering)],
+                blending: self.blending,
+                scissor: None,
+            };
+            graphics.stream.batch_optimized(batch);
+            for quad in quads.iter().filter(|quad| quad.page as usize == page) {
+                let min = origin + quad.position * self.scale;
+                let max = min + quad.size * self.scale;
+                let [uv_min, uv_max] = quad.uvs;
+                graphics.stream.extend(
+                    [
+                        Vertex {
+                            position: [min.x, min.y],
+                            uv: [uv_min.x, uv_min.y, 0.0],
+                            color,
+                        },
+                        Vertex {
+                            position: [max.x, min.y],
+                            uv: [uv_max.x, uv_min.y, 0.0],
+                            color,
+                        },
+                        Vertex {
+                            position: [max.x, max.y],
+                            uv: [uv_max.x, uv_max.y, 0.0],
+                            color,
+                        },
+                        Vertex {
+                            position: [min.x, max.y],
+                            uv: [uv_min.x, uv_max.y, 0.0],
+                            color,
+                        },
+                    ],
+                    [Triangle { a: 0, b: 1, c: 2 }, Triangle { a: 2, b: 3, c: 0 }],
+                );
+            }
+        }
+    }
+}
+
+fn split_text_line(line: &str) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    line.split(move |character: char| {
+        if character == '"' {
+            quoted = !quoted;
+        }
+        character.is_whitespace() && !quoted
+    })
+    .filter(|part| !part.is_empty())
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, Box<dyn Error>> {
+    Ok(u16::from_le_bytes(
+        bytes
+            .get(offset..offset + 2)
+            .ok_or("Truncated binary BMFont data")?
+            .try_into()?,
+    ))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, Box<dyn Error>> {
+    Ok(u32::from_le_bytes(
+        bytes
+            .get(offset..offset + 4)
+            .ok_or("Truncated binary BMFont data")?
+            .try_into()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitmap_font() {
+        let text = BmFont::parse(
+            br#"info face="Pixel Font" size=8
+common lineHeight=10 base=8 scaleW=64 scaleH=32 pages=1
+page id=0 file="pixel_0.png"
+chars count=2
+char id=65 x=0 y=0 width=6 height=8 xoffset=0 yoffset=2 xadvance=7 page=0 chnl=15
+char id=86 x=8 y=0 width=6 height=8 xoffset=0 yoffset=2 xadvance=7 page=0 chnl=15
+kernings count=1
+kerning first=65 second=86 amount=-1
+"#,
+        )
+        .unwrap();
+        let xml = BmFont::parse(
+            br#"<?xml version="1.0"?>
+<font>
+  <info face="Pixel Font" size="8"/>
+  <common lineHeight="10" base="8" scaleW="64" scaleH="32" pages="1"/>
+  <pages><page id="0" file="pixel_0.png"/></pages>
+  <chars count="2">
+    <char id="65" x="0" y="0" width="6" height="8" xoffset="0" yoffset="2" xadvance="7" page="0" chnl="15"/>
+    <char id="86" x="8" y="0" width="6" height="8" xoffset="0" yoffset="2" xadvance="7" page="0" chnl="15"/>
+  </chars>
+  <kernings count="1"><kerning first="65" second="86" amount="-1"/></kernings>
+</font>"#,
+        )
+        .unwrap();
+        assert_eq!(text, xml);
+        assert_eq!(text.pages, vec!["pixel_0.png".to_owned()]);
+        assert_eq!(text.kerning('A', 'V'), -1);
+        assert_eq!(text.measure("AV"), Vec2::new(13.0, 10.0));
+        assert_eq!(text.measure("A\nAV"), Vec2::new(13.0, 20.0));
+
+        let quads = text.layout("AV");
+        assert_eq!(quads.len(), 2);
+        assert_eq!(quads[1].position, Vec2::new(6.0, 2.0));
+        assert_eq!(
+            quads[1].uvs,
+            [Vec2::new(0.125, 0.0), Vec2::new(0.21875, 0.25)]
+        );
+
+        let mut binary = b"BMF\x03".to_vec();
+        let mut block = |kind: u8, data: Vec<u8>| {
+            binary.push(kind);
+            binary.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            binary.extend(data);
+        };
+        block(
+            2,
+            [10u16, 8, 64, 32, 1]
+                .into_iter()
+                .flat_map(u16::to_le_bytes)
+                .chain([0, 0, 0, 0, 0])
+                .collect(),
+        );
+        block(3, b"pixel_0.png\0".to_vec());
+        block(
+            4,
+            [(65u32, 0u16), (86, 8)]
+                .into_iter()
+                .flat_map(|(id, x)| {
+                    id.to_le_bytes()
+                        .into_iter()
+                        .chain([x, 0, 6, 8, 0, 2, 7].into_iter().flat_map(u16::to_le_bytes))
+                        .chain([0, 15])
+                })
+                .collect(),
+        );
+        block(
+            5,
+            65u32
+                .to_le_bytes()
+                .into_iter()
+                .chain(86u32.to_le_bytes())
+                .chain((-1i16).to_le_bytes())
+                .collect(),
+        );
+        assert_eq!(BmFont::parse(&binary).unwrap(), text);
+
+        let mut fonts = BmFontMap::default();
+        fonts.insert(
+            "pixel",
+            BmFontFace {
+                font: Arc::new(text),
+                textures: vec!["fonts/pixel_0.png".to_owned()],
+                filtering: GlowTextureFiltering::Linear,
+            },
+        );
+        let label = fonts.text("pixel", "AV").unwrap().scale(2.0);
+        assert_eq!(label.textures, vec!["fonts/pixel_0.png".to_owned()]);
+        assert_eq!(label.filtering, GlowTextureFiltering::Linear);
+        assert_eq!(label.size(), Vec2::new(26.0, 20.0));
+        assert!(fonts.text("missing", "AV").is_none());
+    }
+}
@@ -1,8 +1,13 @@
-use serde::{Deserialize, Serialize};
+use crate::{audio::AudioConfig, gamepad::GamepadMappings};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use spitfire_glow::app::AppConfig;
-use std::{error::Error, path::Path};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+use toml::{Table, Value};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub width: u32,
     pub height: u32,
@@ -11,6 +16,16 @@ pub struct Config {
     pub vsync: bool,
     pub double_buffer: Option<bool>,
     pub hardware_acceleration: Option<bool>,
+    #[serde(default)]
+    pub audio: AudioConfig,
+    /// Extra SDL gamepad mapping lines, e.g. added by players for their device.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gamepad_mappings: Vec<String>,
+    /// Game specific sections, keyed by section name.
+    #[serde(flatten)]
+    pub sections: Table,
+    #[serde(skip)]
+    layers: ConfigLayers,
 }
 
 impl Default for Config {
@@ -23,6 +38,10 @@ impl Default for Config {
             vsync: Self::default_vsync(),
             double_buffer: Default::default(),
             hardware_acceleration: Default::default(),
+            audio: Default::default(),
+            gamepad_mappings: Default::default(),
+            sections: Default::default(),
+            layers: Default::default(),
         }
     }
 }
@@ -45,11 +64,87 @@ impl Config {
     }
 
     pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
-        Self::load_from_str(&std::fs::read_to_string(path).unwrap_or_default())
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|error| format!("Could not read config file: {:?}. {}", path, error))?;
+        Self::load_from_str(&content)
     }
 
     pub fn load_from_str(content: &str) -> Result<Self, Box<dyn Error>> {
-        Ok(toml::from_str(content)?)
+        ConfigLoader::default().shipped_str(content).load()
+    }
+
+    pub fn with_section<T: Serialize>(
+        mut self,
+        name: impl ToString,
+        value: &T,
+    ) -> Result<Self, Box<dyn Error>> {
+        self.set_section(name, value)?;
+        Ok(self)
+    }
+
+    pub fn has_section(&self, name: &str) -> bool {
+        self.sections.contains_key(name)
+    }
+
+    /// Reads typed section, or its default value when section is missing.
+    pub fn section<T: DeserializeOwned + Default>(&self, name: &str) -> Result<T, Box<dyn Error>> {
+        match self.sections.get(name) {
+            Some(value) => Ok(T::deserialize(value.clone())
+                .map_err(|error| format!("Invalid config section `{}`: {}", name, error))?),
+            None => Ok(T::default()),
+        }
+    }
+
+    pub fn set_section<T: Serialize>(
+        &mut self,
+        name: impl ToString,
+        value: &T,
+    ) -> Result<(), Box<dyn Error>> {
+        let name = name.to_string();
+        let value = Value::try_from(value)
+            .map_err(|error| format!("Could not serialize config section `{}`: {}", name, error))?;
+        self.sections.insert(name, value);
+        Ok(())
+    }
+
+    pub fn user_file(&self) -> Option<&Path> {
+        self.layers.user_path.as_deref()
+    }
+
+    /// Writes values that differ from defaults and shipped config into user config file.
+    /// Values forced by environment or command line are not persisted.
+    pub fn save_user(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(path) = self.layers.user_path.clone() else {
+            return Err("Config has no user file to save into".into());
+        };
+        let Value::Table(current) = Value::try_from(&*self)? else {
+            return Err("Config is not serialized as table".into());
+        };
+        let user = user_diff(
+            &current,
+            &self.layers.base,
+            &self.layers.user,
+            &self.layers.overrides,
+        );
+        if let Some(directory) = path.parent() {
+            if !directory.as_os_str().is_empty() {
+                std::fs::create_dir_all(directory)?;
+            }
+        }
+        std::fs::write(&path, toml::to_string_pretty(&user)?)
+            .map_err(|error| format!("Could not write user config: {:?}. {}", path, error))?;
+        self.layers.user = user;
+        Ok(())
+    }
+
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, self.save_to_string()?)?;
+        Ok(())
+    }
+
+    pub fn save_to_string(&self) -> Result<String, Box<dyn Error>> {
+        Ok(toml::to_string_pretty(self)?)
     }
 
     pub fn to_app_config(&self, name: impl ToString) -> AppConfig {
@@ -67,3 +162,443 @@ impl Config {
         }
     }
 }
+
+/// Platform specific directory for per-user config files of given application.
+pub fn user_config_directory(application: &str) -> Option<PathBuf> {
+    let root = if cfg!(target_os = "windows") {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(std::env::var_os("HOME")?).join("Library/Application Support")
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))?
+    };
+    Some(root.join(application))
+}
+
+#[derive(Debug, Default, Clone)]
+struct ConfigLayers {
+    /// Defaults merged with shipped config.
+    base: Table,
+    user: Table,
+    /// Environment and command line values.
+    overrides: Table,
+    user_path: Option<PathBuf>,
+}
+
+/// Builds config from layers, where later ones override earlier ones:
+/// defaults, shipped config, user config file, environment variables, command line.
+pub struct ConfigLoader {
+    defaults: Config,
+    shipped: Vec<(String, ConfigSource)>,
+    user_path: Option<PathBuf>,
+    env_prefix: Option<String>,
+    args: Vec<String>,
+    #[allow(clippy::type_complexity)]
+    validators: Vec<(String, Box<dyn Fn(&Value) -> Result<(), String>>)>,
+}
+
+enum ConfigSource {
+    Content(String),
+    File(PathBuf),
+}
+
+impl Default for ConfigLoader {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+impl ConfigLoader {
+    pub fn new(defaults: Config) -> Self {
+        Self {
+            defaults,
+            shipped: Default::default(),
+            user_path: None,
+            env_prefix: None,
+            args: Default::default(),
+            validators: Default::default(),
+        }
+    }
+
+    /// Registers typed game section, which default value becomes part of defaults layer
+    /// and which gets validated after all layers are merged.
+    pub fn section<T: Serialize + DeserializeOwned + Default + 'static>(
+        mut self,
+        name: impl ToString,
+    ) -> Self {
+        let name = name.to_string();
+        if let Ok(value) = Value::try_from(T::default()) {
+            self.defaults.sections.insert(name.clone(), value);
+        }
+        self.validators.push((
+            name,
+            Box::new(|value| {
+                T::deserialize(value.clone())
+                    .map(|_| ())
+                    .map_err(|error| error.to_string())
+            }),
+        ));
+        self
+    }
+
+    pub fn shipped_str(mut self, content: impl ToString) -> Self {
+        self.shipped.push((
+            "shipped config".to_owned(),
+            ConfigSource::Content(content.to_string()),
+        ));
+        self
+    }
+
+    pub fn shipped_file(mut self, path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        self.shipped
+            .push((format!("{:?}", path), ConfigSource::File(path)));
+        self
+    }
+
+    /// Optional per-user file, also used as target of `Config::save_user`.
+    pub fn user_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.user_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Reads `<PREFIX>_<KEY>` variables, where `__` separates nested keys,
+    /// for example `GAME_AUDIO__BUSES__MUSIC__VOLUME=0.5`.
+    pub fn env(mut self, prefix: impl ToString) -> Self {
+        self.env_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Reads `--key value`, `--key=value`, `--flag` and `--no-flag` arguments,
+    /// where dots separate nested keys. `--windowed` is alias of `--no-fullscreen`.
+    pub fn args(mut self, args: impl IntoIterator<Item = impl ToString>) -> Self {
+        self.args = args.into_iter().map(|arg| arg.to_string()).collect();
+        self
+    }
+
+    pub fn load(self) -> Result<Config, Box<dyn Error>> {
+        let Value::Table(defaults) = Value::try_from(&self.defaults)? else {
+            return Err("Config is not serialized as table".into());
+        };
+        let mut base = defaults.clone();
+        for (name, source) in &self.shipped {
+            let layer = match source {
+                ConfigSource::Content(content) => parse_layer(content, name)?,
+                ConfigSource::File(path) => {
+                    let content = std::fs::read_to_string(path).map_err(|error| {
+                        format!("Could not read config file: {:?}. {}", path, error)
+                    })?;
+                    parse_layer(&content, name)?
+                }
+            };
+            validate_layer(&layer, &defaults, "", name)?;
+            merge_table(&mut base, layer);
+        }
+
+        let user = match &self.user_path {
+            Some(path) => match std::fs::read_to_string(path) {
+                Ok(content) => {
+                    let name = format!("{:?}", path);
+                    let layer = parse_layer(&content, &name)?;
+                    validate_layer(&layer, &defaults, "", &name)?;
+                    layer
+                }
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        std::io::ErrorKind::NotFound | std::io::ErrorKind::Unsupported
+                    ) =>
+                {
+                    Table::new()
+                }
+                Err(error) => {
+                    return Err(format!("Could not read user config: {:?}. {}", path, error).into())
+                }
+            },
+            None => Table::new(),
+        };
+
+        let mut overrides = Table::new();
+        if let Some(prefix) = &self.env_prefix {
+            let prefix = format!("{}_", prefix);
+            // Variables that are not valid unicode cannot be config values, so skip them.
+            let mut variables = std::env::vars_os()
+                .filter_map(|(name, value)| {
+                    Some((name.into_string().ok()?, value.into_string().ok()?))
+                })
+                .filter(|(name, _)| name.starts_with(&prefix))
+                .collect::<Vec<_>>();
+            variables.sort();
+            for (name, value) in variables {
+                let key = name[prefix.len()..].to_lowercase().replace("__", ".");
+                let mut layer = Table::new();
+                set_path(&mut layer, &key, parse_value(&value))?;
+                validate_layer(
+                    &layer,
+                    &defaults,
+                    "",
+                    &format!("environment variable {}", name),
+                )?;
+                merge_table(&mut overrides, layer);
+            }
+        }
+        let mut layer = Table::new();
+        for (key, value) in parse_args(&self.args) {
+            set_path(&mut layer, &key, value)?;
+        }
+        validate_layer(&layer, &defaults, "", "command line")?;
+        merge_table(&mut overrides, layer);
+
+        let mut merged = base.clone();
+        merge_table(&mut merged, user.clone());
+        merge_table(&mut merged, overrides.clone());
+        let mut result = Config::deserialize(Value::Table(merged))
+            .map_err(|error| format!("Invalid config: {}", error))?;
+        result.validate()?;
+        for (name, validator) in &self.validators {
+            if let Some(value) = result.sections.get(name) {
+                validator(value)
+                    .map_err(|error| format!("Invalid config section `{}`: {}", name, error))?;
+            }
+        }
+        result.layers = ConfigLayers {
+            base,
+            user,
+            overrides,
+            user_path: self.user_path,
+        };
+        Ok(result)
+    }
+}
+
+impl Config {
+    pub fn parse_gamepad_mappings(&self) -> Result<GamepadMappings, Box<dyn Error>> {
+        GamepadMappings::parse(&self.gamepad_mappings.join("\n"))
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.width == 0 {
+            return Err("Invalid config value `width`: must be greater than zero".into());
+        }
+        if self.height == 0 {
+            return Err("Invalid config value `height`: must be greater than zero".into());
+        }
+        self.parse_gamepad_mappings()
+            .map_err(|error| format!("Invalid config value `gamepad_mappings`: {}", error))?;
+        Ok(())
+    }
+}
+
+fn parse_layer(content: &str, name: &str) -> Result<Table, Box<dyn Error>> {
+    Ok(content
+        .parse::<Table>()
+        .map_err(|error| format!("Could not parse {}: {}", name, error))?)
+}
+
+/// Parses TOML value literal, falling back to plain string.
+fn parse_value(text: &str) -> Value {
+    format!("value = {}", text)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(text.to_owned()))
+}
+
+fn parse_args(args: &[String]) -> Vec<(String, Value)> {
+    let mut result = Vec::new();
+    let mut iter = args.iter().peekable();
+    while let Some(arg) = iter.next() {
+        let Some(arg) = arg.strip_prefix("--") else {
+            continue;
+        };
+        if let Some((key, value)) = arg.split_once('=') {
+            result.push((key.replace('-', "_"), parse_value(value)));
+        } else if arg == "windowed" {
+            result.push(("fullscreen".to_owned(), Value::Boolean(false)));
+        } else if let Some(key) = arg.strip_prefix("no-") {
+            result.push((key.replace('-', "_"), Value::Boolean(false)));
+        } else if let Some(value) = iter.next_if(|value| !value.starts_with("--")) {
+            result.push((arg.replace('-', "_"), parse_value(value)));
+        } else {
+            result.push((arg.replace('-', "_"), Value::Boolean(true)));
+        }
+    }
+    result
+}
+
+fn set_path(table: &mut Table, path: &str, value: Value) -> Result<(), Box<dyn Error>> {
+    let mut parts = path.split('.').peekable();
+    let mut current = table;
+    while let Some(part) = parts.next() {
+        if part.is_empty() {
+            return Err(format!("Invalid config key `{}`", path).into());
+        }
+        if parts.peek().is_none() {
+            current.insert(part.to_owned(), value);
+            break;
+        }
+        let entry = current
+            .entry(part.to_owned())
+            .or_insert_with(|| Value::Table(Table::new()));
+        let Value::Table(next) = entry else {
+            return Err(format!("Invalid config key `{}`: `{}` is not a table", path, part).into());
+        };
+        current = next;
+    }
+    Ok(())
+}
+
+fn merge_table(target: &mut Table, source: Table) {
+    for (key, value) in source {
+        match (target.get_mut(&key), value) {
+            (Some(Value::Table(target)), Value::Table(source)) => merge_table(target, source),
+            (_, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Makes sure layer values match types of default values, reporting offending key.
+fn validate_layer(
+    layer: &Table,
+    defaults: &Table,
+    prefix: &str,
+    source: &str,
+) -> Result<(), Box<dyn Error>> {
+    for (key, value) in layer {
+        let path = if prefix.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        let Some(default) = defaults.get(key) else {
+            continue;
+        };
+        match (default, value) {
+            (Value::Table(defaults), Value::Table(layer)) => {
+                validate_layer(layer, defaults, &path, source)?;
+            }
+            (Value::Float(_), Value::Integer(_)) => {}
+            (default, value) if default.same_type(value) => {}
+            (default, value) => {
+                return Err(format!(
+                    "Invalid config value `{}` in {}: expected {}, found {}",
+                    path,
+                    source,
+                    default.type_str(),
+                    value.type_str()
+                )
+                .into());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn user_diff(current: &Table, base: &Table, user: &Table, overrides: &Table) -> Table {
+    let empty = Table::new();
+    let mut result = Table::new();
+    for (key, value) in current {
+        if let Value::Table(current) = value {
+            let sub = |table: &Table| match table.get(key) {
+                Some(Value::Table(table)) => table.clone(),
+                _ => empty.clone(),
+            };
+            let table = user_diff(current, &sub(base), &sub(user), &sub(overrides));
+            if !table.is_empty() {
+                result.insert(key.to_owned(), Value::Table(table));
+            }
+        } else if overrides.get(key) == Some(value) {
+            if let Some(value) = user.get(key) {
+                result.insert(key.to_owned(), value.clone());
+            }
+        } else if base.get(key) != Some(value) {
+            result.insert(key.to_owned(), value.clone());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layered_config() {
+        let config = ConfigLoader::default()
+            .shipped_str("width = 800\nfullscreen = true\n[audio.buses.music]\nvolume = 0.5")
+            .args([
+                "--windowed",
+                "--width",
+                "1280",
+                "--audio.buses.music.volume=1",
+            ])
+            .load()
+            .unwrap();
+        assert_eq!(config.width, 1280);
+        assert_eq!(config.height, 576);
+        assert!(!config.fullscreen);
+
+        let error = ConfigLoader::default()
+            .args(["--height", "tall"])
+            .load()
+            .unwrap_err();
+        assert!(error.to_string().contains("`height`"));
+
+        let error = ConfigLoader::default()
+            .shipped_str("width = 0")
+            .load()
+            .unwrap_err();
+        assert!(error.to_string().contains("`width`"));
+
+        let mut config = ConfigLoader::default()
+            .shipped_str("width = 800")
+            .args(["--windowed"])
+            .load()
+            .unwrap();
+        config.height = 600;
+        let Value::Table(current) = Value::try_from(&config).unwrap() else {
+            unreachable!()
+        };
+        let layers = &config.layers;
+        let user = user_diff(&current, &layers.base, &layers.user, &layers.overrides);
+        assert_eq!(user.len(), 1);
+        assert_eq!(user.get("height"), Some(&Value::Integer(600)));
+    }
+
+    #[test]
+    fn test_config_sections() {
+        #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+        #[serde(default)]
+        struct Difficulty {
+            enemies: usize,
+            permadeath: bool,
+        }
+
+        let config = ConfigLoader::default()
+            .section::<Difficulty>("difficulty")
+            .shipped_str("[difficulty]\nenemies = 6\n[custom]\nvalue = 1")
+            .args(["--difficulty.permadeath"])
+            .load()
+            .unwrap();
+        assert_eq!(
+            config.section::<Difficulty>("difficulty").unwrap(),
+            Difficulty {
+                enemies: 6,
+                permadeath: true,
+            }
+        );
+        assert!(config.has_section("custom"));
+        assert!(config.save_to_string().unwrap().contains("[difficulty]"));
+
+        let error = ConfigLoader::default()
+            .section::<Difficulty>("difficulty")
+            .args(["--difficulty.enemies", "many"])
+            .load()
+            .unwrap_err();
+        assert!(error.to_string().contains("`difficulty.enemies`"));
+    }
+}
@@ -1,20 +1,39 @@
 use crate::{
     audio::Audio,
+    bitmap_font::BmFontMap,
+    config::Config,
     game::{GameGlobals, GameStateChange},
+    input::{capture::InputCapture, prompts::InputDeviceTracker, touch::TouchInput},
+    localization::Localization,
 };
 use keket::database::AssetDatabase;
 use spitfire_draw::{context::DrawContext, utils::Vertex};
 use spitfire_glow::graphics::Graphics;
 use spitfire_gui::context::GuiContext;
 use spitfire_input::InputContext;
+use std::error::Error;
 
 pub struct GameContext<'a> {
     pub graphics: &'a mut Graphics<Vertex>,
     pub draw: &'a mut DrawContext,
+    pub bitmap_fonts: &'a mut BmFontMap,
     pub gui: &'a mut GuiContext,
     pub input: &'a mut InputContext,
+    pub input_capture: &'a mut InputCapture,
+    pub touch: &'a mut TouchInput,
+    pub input_device: &'a mut InputDeviceTracker,
     pub state_change: &'a mut GameStateChange,
     pub assets: &'a mut AssetDatabase,
     pub audio: &'a mut Audio,
+    pub localization: &'a mut Localization,
     pub globals: &'a mut GameGlobals,
+    pub config: &'a mut Config,
+}
+
+impl GameContext<'_> {
+    /// Saves user config, with audio bus volumes and mutes taken from current audio state.
+    pub fn save_user_config(&mut self) -> Result<(), Box<dyn Error>> {
+        self.config.audio = self.audio.config();
+        self.config.save_user()
+    }
 }
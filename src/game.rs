@@ -1,10 +1,15 @@
 use crate::{
     assets::{
-        font::FontAssetSubsystem, shader::ShaderAssetSubsystem, sound::SoundAssetSubsystem,
-        texture::TextureAssetSubsystem,
+        bmfont::BmFontAssetSubsystem, font::FontAssetSubsystem,
+        gamepad::GamepadMappingsAssetSubsystem, localization::LocalizationAssetSubsystem,
+        shader::ShaderAssetSubsystem, sound::SoundAssetSubsystem, texture::TextureAssetSubsystem,
     },
     audio::Audio,
+    bitmap_font::BmFontMap,
+    config::Config,
     context::GameContext,
+    input::{capture::InputCapture, prompts::InputDeviceTracker, touch::TouchInput},
+    localization::Localization,
 };
 #[cfg(not(target_arch = "wasm32"))]
 use glutin::{event::Event, window::Window};
@@ -107,16 +112,22 @@ pub struct GameInstance {
     pub text_shader: &'static str,
     pub input_maintain_on_fixed_step: bool,
     draw: DrawContext,
+    bitmap_fonts: BmFontMap,
     gui: GuiContext,
     input: InputContext,
+    input_capture: InputCapture,
+    touch: TouchInput,
+    input_device: InputDeviceTracker,
     assets: AssetDatabase,
     audio: Audio,
+    localization: Localization,
     timer: Instant,
     fixed_timer: Instant,
     states: Vec<Box<dyn GameState>>,
     state_change: GameStateChange,
     subsystems: Vec<Box<dyn GameSubsystem>>,
     globals: GameGlobals,
+    config: Config,
 }
 
 impl Default for GameInstance {
@@ -128,10 +139,15 @@ impl Default for GameInstance {
             text_shader: "text",
             input_maintain_on_fixed_step: true,
             draw: Default::default(),
+            bitmap_fonts: Default::default(),
             gui: Default::default(),
             input: Default::default(),
+            input_capture: Default::default(),
+            touch: Default::default(),
+            input_device: Default::default(),
             assets: Default::default(),
             audio: Default::default(),
+            localization: Default::default(),
             timer: Instant::now(),
             fixed_timer: Instant::now(),
             states: Default::default(),
@@ -140,9 +156,13 @@ impl Default for GameInstance {
                 Box::new(ShaderAssetSubsystem),
                 Box::new(TextureAssetSubsystem),
                 Box::new(FontAssetSubsystem),
-                Box::new(SoundAssetSubsystem),
+                Box::new(BmFontAssetSubsystem),
+                Box::<SoundAssetSubsystem>::default(),
+                Box::<LocalizationAssetSubsystem>::default(),
+                Box::new(GamepadMappingsAssetSubsystem),
             ],
             globals: Default::default(),
+            config: Default::default(),
         }
     }
 }
@@ -195,6 +215,20 @@ impl GameInstance {
         self
     }
 
+    pub fn with_localization(mut self, localization: Localization) -> Self {
+        self.localization = localization;
+        self
+    }
+
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.audio.apply_config(&config.audio);
+        if let Ok(mappings) = config.parse_gamepad_mappings() {
+            mappings.register_config();
+        }
+        self.config = config;
+        self
+    }
+
     pub fn setup_assets(mut self, f: impl FnOnce(&mut AssetDatabase)) -> Self {
         f(&mut self.assets);
         self
@@ -216,17 +250,27 @@ impl GameInstance {
                 GameContext {
                     graphics,
                     draw: &mut self.draw,
+                    bitmap_fonts: &mut self.bitmap_fonts,
                     gui: &mut self.gui,
                     input: &mut self.input,
+                    input_capture: &mut self.input_capture,
+                    touch: &mut self.touch,
+                    input_device: &mut self.input_device,
                     state_change: &mut self.state_change,
                     assets: &mut self.assets,
                     audio: &mut self.audio,
+                    localization: &mut self.localization,
                     globals: &mut self.globals,
+                    config: &mut self.config,
                 },
                 delta_time,
             );
         }
         self.assets.maintain().unwrap();
+        if self.audio.listener.follow_main_camera {
+            self.audio.listener.position = graphics.main_camera.world_rectangle().center();
+        }
+        self.audio.maintain(delta_time);
 
         if let Some(state) = self.states.last_mut() {
             self.timer = Instant::now();
@@ -234,12 +278,18 @@ impl GameInstance {
                 GameContext {
                     graphics,
                     draw: &mut self.draw,
+                    bitmap_fonts: &mut self.bitmap_fonts,
                     gui: &mut self.gui,
                     input: &mut self.input,
+                    input_capture: &mut self.input_capture,
+                    touch: &mut self.touch,
+                    input_device: &mut self.input_device,
                     state_change: &mut self.state_change,
                     assets: &mut self.assets,
                     audio: &mut self.audio,
+                    localization: &mut self.localization,
                     globals: &mut self.globals,
+                    config: &mut self.config,
                 },
                 delta_time,
             );
@@ -253,12 +303,18 @@ impl GameInstance {
                     GameContext {
                         graphics,
                         draw: &mut self.draw,
+                        bitmap_fonts: &mut self.bitmap_fonts,
                         gui: &mut self.gui,
                         input: &mut self.input,
+                        input_capture: &mut self.input_capture,
+                        touch: &mut self.touch,
+                        input_device: &mut self.input_device,
                         state_change: &mut self.state_change,
                         assets: &mut self.assets,
                         audio: &mut self.audio,
+                        localization: &mut self.localization,
                         globals: &mut self.globals,
+                        config: &mut self.config,
                     },
                     fixed_delta_time,
                 );
@@ -275,12 +331,18 @@ impl GameInstance {
             state.draw(GameContext {
                 graphics,
                 draw: &mut self.draw,
+                bitmap_fonts: &mut self.bitmap_fonts,
                 gui: &mut self.gui,
                 input: &mut self.input,
+                input_capture: &mut self.input_capture,
+                touch: &mut self.touch,
+                input_device: &mut self.input_device,
                 state_change: &mut self.state_change,
                 assets: &mut self.assets,
                 audio: &mut self.audio,
+                localization: &mut self.localization,
                 globals: &mut self.globals,
+                config: &mut self.config,
             });
         }
         self.gui.begin_frame();
@@ -288,12 +350,18 @@ impl GameInstance {
             state.draw_gui(GameContext {
                 graphics,
                 draw: &mut self.draw,
+                bitmap_fonts: &mut self.bitmap_fonts,
                 gui: &mut self.gui,
                 input: &mut self.input,
+                input_capture: &mut self.input_capture,
+                touch: &mut self.touch,
+                input_device: &mut self.input_device,
                 state_change: &mut self.state_change,
                 assets: &mut self.assets,
                 audio: &mut self.audio,
+                localization: &mut self.localization,
                 globals: &mut self.globals,
+                config: &mut self.config,
             });
         }
         self.gui.end_frame(
@@ -306,6 +374,8 @@ impl GameInstance {
         self.draw.end_frame();
         if !self.input_maintain_on_fixed_step || fixed_step {
             self.input.maintain();
+            self.touch.maintain();
+            self.input_device.maintain();
         }
 
         match std::mem::take(&mut self.state_change) {
@@ -315,23 +385,35 @@ impl GameInstance {
                     state.exit(GameContext {
                         graphics,
                         draw: &mut self.draw,
+                        bitmap_fonts: &mut self.bitmap_fonts,
                         gui: &mut self.gui,
                         input: &mut self.input,
+                        input_capture: &mut self.input_capture,
+                        touch: &mut self.touch,
+                        input_device: &mut self.input_device,
                         state_change: &mut self.state_change,
                         assets: &mut self.assets,
                         audio: &mut self.audio,
+                        localization: &mut self.localization,
                         globals: &mut self.globals,
+                        config: &mut self.config,
                     });
                 }
                 state.enter(GameContext {
                     graphics,
                     draw: &mut self.draw,
+                    bitmap_fonts: &mut self.bitmap_fonts,
                     gui: &mut self.gui,
                     input: &mut self.input,
+                    input_capture: &mut self.input_capture,
+                    touch: &mut self.touch,
+                    input_device: &mut self.input_device,
                     state_change: &mut self.state_change,
                     assets: &mut self.assets,
                     audio: &mut self.audio,
+                    localization: &mut self.localization,
                     globals: &mut self.globals,
+                    config: &mut self.config,
                 });
                 self.states.push(state);
                 self.timer = Instant::now();
@@ -340,12 +422,18 @@ impl GameInstance {
                 state.enter(GameContext {
                     graphics,
                     draw: &mut self.draw,
+                    bitmap_fonts: &mut self.bitmap_fonts,
                     gui: &mut self.gui,
                     input: &mut self.input,
+                    input_capture: &mut self.input_capture,
+                    touch: &mut self.touch,
+                    input_device: &mut self.input_device,
                     state_change: &mut self.state_change,
                     assets: &mut self.assets,
                     audio: &mut self.audio,
+                    localization: &mut self.localization,
                     globals: &mut self.globals,
+                    config: &mut self.config,
                 });
                 self.states.push(state);
                 self.timer = Instant::now();
@@ -355,12 +443,18 @@ impl GameInstance {
                     state.exit(GameContext {
                         graphics,
                         draw: &mut self.draw,
+                        bitmap_fonts: &mut self.bitmap_fonts,
                         gui: &mut self.gui,
                         input: &mut self.input,
+                        input_capture: &mut self.input_capture,
+                        touch: &mut self.touch,
+                        input_device: &mut self.input_device,
                         state_change: &mut self.state_change,
                         assets: &mut self.assets,
                         audio: &mut self.audio,
+                        localization: &mut self.localization,
                         globals: &mut self.globals,
+                        config: &mut self.config,
                     });
                 }
                 self.timer = Instant::now();
@@ -370,7 +464,11 @@ impl GameInstance {
 
     pub fn process_event(&mut self, event: &Event<()>) -> bool {
         if let Event::WindowEvent { event, .. } = event {
-            self.input.on_event(event);
+            self.input_device.on_event(event);
+            if !self.input_capture.on_event(event) {
+                self.input.on_event(event);
+                self.touch.on_event(event, &mut self.input);
+            }
         }
         !self.states.is_empty() || !matches!(self.state_change, GameStateChange::Continue)
     }
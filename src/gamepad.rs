@@ -1,21 +1,400 @@
-use gilrs::{Axis, Button, GamepadId, Gilrs};
+use crate::input::actions::InputBinding;
+use anim8::spline::Spline;
+use gilrs::{Axis, Button, EventType, Gilrs, GilrsBuilder, PowerInfo};
 use spitfire_input::{InputActionOrAxisRef, InputAxis};
 use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
+    error::Error,
     rc::Rc,
+    sync::Arc,
 };
+use vek::Vec2;
+
+pub const GAMEPAD_BUTTONS: [Button; 19] = [
+    Button::South,
+    Button::East,
+    Button::North,
+    Button::West,
+    Button::C,
+    Button::Z,
+    Button::LeftTrigger,
+    Button::LeftTrigger2,
+    Button::RightTrigger,
+    Button::RightTrigger2,
+    Button::Select,
+    Button::Start,
+    Button::Mode,
+    Button::LeftThumb,
+    Button::RightThumb,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+];
+
+pub const GAMEPAD_AXES: [Axis; 8] = [
+    Axis::LeftStickX,
+    Axis::LeftStickY,
+    Axis::LeftZ,
+    Axis::RightStickX,
+    Axis::RightStickY,
+    Axis::RightZ,
+    Axis::DPadX,
+    Axis::DPadY,
+];
+
+/// Identifier of either real gamepad device or virtual one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadId {
+    Device(gilrs::GamepadId),
+    Virtual(usize),
+}
+
+/// Default mappings kept in layers, so config mappings override shipped ones no matter
+/// which got registered first.
+#[derive(Default)]
+struct DefaultMappings {
+    assets: GamepadMappings,
+    config: GamepadMappings,
+    revision: usize,
+}
+
+thread_local! {
+    static DEFAULT_MAPPINGS: RefCell<DefaultMappings> = Default::default();
+}
+
+/// SDL `gamecontrollerdb.txt` style mapping lines, for gamepads with unknown layouts.
+/// Mappings apply when gamepad manager gets created or reloads mappings, next to ones
+/// from `SDL_GAMECONTROLLERCONFIG` environment variable.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GamepadMappings {
+    lines: Vec<String>,
+}
+
+impl GamepadMappings {
+    /// Parses mapping lines, skipping empty lines and comments.
+    pub fn parse(content: &str) -> Result<Self, Box<dyn Error>> {
+        let mut result = Self::default();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            result.add(line).map_err(|error| {
+                format!("Invalid gamepad mapping at line {}: {}", index + 1, error)
+            })?;
+        }
+        Ok(result)
+    }
+
+    /// Adds mapping line, replacing existing one for the same gamepad and platform.
+    pub fn add(&mut self, line: &str) -> Result<(), Box<dyn Error>> {
+        let line = line.trim();
+        let mut parts = line.split(',');
+        let guid = parts.next().unwrap_or_default();
+        if guid != "xinput" && (guid.len() != 32 || !guid.chars().all(|c| c.is_ascii_hexdigit())) {
+            return Err(format!("Invalid gamepad GUID `{}`", guid).into());
+        }
+        let name = parts
+            .next()
+            .filter(|name| !name.is_empty())
+            .ok_or("Missing gamepad name")?;
+        if let Some(part) = parts.find(|part| !part.is_empty() && !part.contains(':')) {
+            return Err(format!("Invalid mapping entry `{}` of `{}`", part, name).into());
+        }
+        let key = Self::key(line);
+        self.lines.retain(|item| Self::key(item) != key);
+        self.lines.push(line.to_owned());
+        Ok(())
+    }
+
+    pub fn extend(&mut self, other: &Self) {
+        for line in &other.lines {
+            let key = Self::key(line);
+            self.lines.retain(|item| Self::key(item) != key);
+            self.lines.push(line.to_owned());
+        }
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Makes mappings used by gamepad managers created with default constructor.
+    pub fn register_default(&self) {
+        DEFAULT_MAPPINGS.with(|mappings| {
+            let mut mappings = mappings.borrow_mut();
+            mappings.assets.extend(self);
+            mappings.revision += 1;
+        });
+    }
+
+    /// Replaces default mappings coming from user config, applied over registered ones.
+    pub fn register_config(&self) {
+        DEFAULT_MAPPINGS.with(|mappings| {
+            let mut mappings = mappings.borrow_mut();
+            mappings.config = self.clone();
+            mappings.revision += 1;
+        });
+    }
+
+    pub fn defaults() -> Self {
+        DEFAULT_MAPPINGS.with(|mappings| {
+            let mappings = mappings.borrow();
+            let mut result = mappings.assets.clone();
+            result.extend(&mappings.config);
+            result
+        })
+    }
+
+    fn defaults_revision() -> usize {
+        DEFAULT_MAPPINGS.with(|mappings| mappings.borrow().revision)
+    }
+
+    fn key(line: &str) -> (&str, &str) {
+        let guid = line.split(',').next().unwrap_or_default();
+        let platform = line
+            .split(',')
+            .find_map(|part| part.strip_prefix("platform:"))
+            .unwrap_or_default();
+        (guid, platform)
+    }
+}
+
+impl std::fmt::Display for GamepadMappings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Layout family of gamepad, used to show matching button prompts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadStyle {
+    #[default]
+    Xbox,
+    PlayStation,
+    Nintendo,
+}
+
+impl GamepadStyle {
+    /// Detects style by USB vendor id, falling back to gamepad name.
+    /// Unknown gamepads are assumed to use Xbox layout, like most PC gamepads.
+    pub fn detect(name: &str, vendor_id: Option<u16>) -> Self {
+        match vendor_id {
+            Some(0x054c) => return Self::PlayStation,
+            Some(0x057e) => return Self::Nintendo,
+            Some(0x045e) => return Self::Xbox,
+            _ => {}
+        }
+        let name = name.to_lowercase();
+        if [
+            "playstation",
+            "dualshock",
+            "dualsense",
+            "ps3",
+            "ps4",
+            "ps5",
+            "sony",
+        ]
+        .iter()
+        .any(|pattern| name.contains(pattern))
+        {
+            Self::PlayStation
+        } else if ["nintendo", "switch", "joy-con", "pro controller"]
+            .iter()
+            .any(|pattern| name.contains(pattern))
+        {
+            Self::Nintendo
+        } else {
+            Self::Xbox
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    PowerChanged(GamepadId, PowerInfo),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadDeadzoneMode {
+    /// Zeroes each axis separately, keeping raw values outside of deadzone.
+    #[default]
+    Axial,
+    /// Zeroes each axis separately, remapping values outside of deadzone to full range.
+    ScaledAxial,
+    /// Zeroes stick within circular deadzone, keeping raw magnitude outside of it.
+    Radial,
+    /// Zeroes stick within circular deadzone, remapping magnitude outside of it to full range.
+    ScaledRadial,
+}
+
+#[derive(Clone, Default)]
+pub enum GamepadResponseCurve {
+    #[default]
+    Linear,
+    Exponential(f32),
+    /// Spline of `[input, output]` points, with inputs sorted from 0 to 1.
+    Custom(Arc<Spline<[f32; 2]>>),
+}
+
+impl std::fmt::Debug for GamepadResponseCurve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Linear => write!(f, "Linear"),
+            Self::Exponential(exponent) => f.debug_tuple("Exponential").field(exponent).finish(),
+            Self::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+impl GamepadResponseCurve {
+    pub fn custom(points: &[[f32; 2]]) -> Option<Self> {
+        Some(Self::Custom(Arc::new(Spline::smooth(points, 1.0).ok()?)))
+    }
+
+    pub fn sample(&self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Exponential(exponent) => value.powf(*exponent),
+            Self::Custom(spline) => {
+                let mut min = 0.0;
+                let mut max = 1.0;
+                for _ in 0..16 {
+                    let time = (min + max) * 0.5;
+                    if spline.sample(time)[0] < value {
+                        min = time;
+                    } else {
+                        max = time;
+                    }
+                }
+                spline.sample((min + max) * 0.5)[1]
+            }
+        }
+    }
+}
+
+/// Processing of raw analog values: deadzones, sensitivity and response curve.
+#[derive(Debug, Clone)]
+pub struct GamepadAxisResponse {
+    pub deadzone: f32,
+    /// Part of range near the edge, which already counts as full tilt.
+    pub outer_deadzone: f32,
+    pub deadzone_mode: GamepadDeadzoneMode,
+    pub sensitivity: f32,
+    pub curve: GamepadResponseCurve,
+}
+
+impl Default for GamepadAxisResponse {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.0,
+            outer_deadzone: 0.0,
+            deadzone_mode: Default::default(),
+            sensitivity: 1.0,
+            curve: Default::default(),
+        }
+    }
+}
+
+impl GamepadAxisResponse {
+    pub fn new(deadzone: f32) -> Self {
+        Self {
+            deadzone,
+            ..Default::default()
+        }
+    }
+
+    pub fn outer_deadzone(mut self, value: f32) -> Self {
+        self.outer_deadzone = value;
+        self
+    }
+
+    pub fn deadzone_mode(mut self, mode: GamepadDeadzoneMode) -> Self {
+        self.deadzone_mode = mode;
+        self
+    }
+
+    pub fn sensitivity(mut self, value: f32) -> Self {
+        self.sensitivity = value;
+        self
+    }
+
+    pub fn curve(mut self, curve: GamepadResponseCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    pub fn apply_value(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        if magnitude < self.deadzone {
+            return 0.0;
+        }
+        let magnitude = match self.deadzone_mode {
+            GamepadDeadzoneMode::Axial | GamepadDeadzoneMode::Radial => self.clamp(magnitude),
+            GamepadDeadzoneMode::ScaledAxial | GamepadDeadzoneMode::ScaledRadial => {
+                self.remap(magnitude)
+            }
+        };
+        self.finish(magnitude).copysign(value)
+    }
+
+    pub fn apply_stick(&self, value: Vec2<f32>) -> Vec2<f32> {
+        let magnitude = value.magnitude();
+        match self.deadzone_mode {
+            GamepadDeadzoneMode::Axial | GamepadDeadzoneMode::ScaledAxial => {
+                Vec2::new(self.apply_value(value.x), self.apply_value(value.y))
+            }
+            _ if magnitude < self.deadzone || magnitude <= f32::EPSILON => Vec2::zero(),
+            GamepadDeadzoneMode::Radial => value / magnitude * self.finish(self.clamp(magnitude)),
+            GamepadDeadzoneMode::ScaledRadial => {
+                value / magnitude * self.finish(self.remap(magnitude))
+            }
+        }
+    }
+
+    fn clamp(&self, magnitude: f32) -> f32 {
+        if magnitude >= 1.0 - self.outer_deadzone {
+            1.0
+        } else {
+            magnitude.min(1.0)
+        }
+    }
+
+    fn remap(&self, magnitude: f32) -> f32 {
+        let range = (1.0 - self.outer_deadzone - self.deadzone).max(f32::EPSILON);
+        ((magnitude - self.deadzone) / range).clamp(0.0, 1.0)
+    }
+
+    fn finish(&self, magnitude: f32) -> f32 {
+        (self.curve.sample(magnitude) * self.sensitivity).clamp(0.0, 1.0)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum GamepadInputAxis {
     Single {
         input: InputActionOrAxisRef,
-        deadzone: f32,
+        response: GamepadAxisResponse,
     },
     Double {
         negative: InputActionOrAxisRef,
         positive: InputActionOrAxisRef,
-        deadzone: f32,
+        response: GamepadAxisResponse,
     },
 }
 
@@ -23,7 +402,7 @@ impl GamepadInputAxis {
     pub fn single(input: impl Into<InputActionOrAxisRef>, deadzone: f32) -> Self {
         Self::Single {
             input: input.into(),
-            deadzone,
+            response: GamepadAxisResponse::new(deadzone),
         }
     }
 
@@ -35,18 +414,354 @@ impl GamepadInputAxis {
         Self::Double {
             negative: negative.into(),
             positive: positive.into(),
-            deadzone,
+            response: GamepadAxisResponse::new(deadzone),
+        }
+    }
+
+    pub fn response(mut self, value: GamepadAxisResponse) -> Self {
+        match &mut self {
+            Self::Single { response, .. } | Self::Double { response, .. } => *response = value,
+        }
+        self
+    }
+}
+
+/// Pair of stick axes processed together, so radial deadzones and curves apply
+/// to stick tilt rather than to each axis separately.
+#[derive(Debug, Clone)]
+pub struct GamepadInputStick {
+    pub x: Axis,
+    pub y: Axis,
+    pub left: InputActionOrAxisRef,
+    pub right: InputActionOrAxisRef,
+    pub down: InputActionOrAxisRef,
+    pub up: InputActionOrAxisRef,
+    pub response: GamepadAxisResponse,
+}
+
+impl GamepadInputStick {
+    pub fn new(
+        x: Axis,
+        y: Axis,
+        left: impl Into<InputActionOrAxisRef>,
+        right: impl Into<InputActionOrAxisRef>,
+        down: impl Into<InputActionOrAxisRef>,
+        up: impl Into<InputActionOrAxisRef>,
+    ) -> Self {
+        Self {
+            x,
+            y,
+            left: left.into(),
+            right: right.into(),
+            down: down.into(),
+            up: up.into(),
+            response: GamepadAxisResponse::new(0.15)
+                .deadzone_mode(GamepadDeadzoneMode::ScaledRadial),
+        }
+    }
+
+    pub fn left_stick(
+        left: impl Into<InputActionOrAxisRef>,
+        right: impl Into<InputActionOrAxisRef>,
+        down: impl Into<InputActionOrAxisRef>,
+        up: impl Into<InputActionOrAxisRef>,
+    ) -> Self {
+        Self::new(Axis::LeftStickX, Axis::LeftStickY, left, right, down, up)
+    }
+
+    pub fn right_stick(
+        left: impl Into<InputActionOrAxisRef>,
+        right: impl Into<InputActionOrAxisRef>,
+        down: impl Into<InputActionOrAxisRef>,
+        up: impl Into<InputActionOrAxisRef>,
+    ) -> Self {
+        Self::new(Axis::RightStickX, Axis::RightStickY, left, right, down, up)
+    }
+
+    pub fn response(mut self, value: GamepadAxisResponse) -> Self {
+        self.response = value;
+        self
+    }
+}
+
+pub(crate) fn write_input(input: &mut InputActionOrAxisRef, value: f32) {
+    match input {
+        InputActionOrAxisRef::Action(input) => {
+            input.set(input.get().change(value > 0.5));
+        }
+        InputActionOrAxisRef::Axis(input) => {
+            input.set(InputAxis(value));
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn write_signed_input(
+    negative: &mut InputActionOrAxisRef,
+    positive: &mut InputActionOrAxisRef,
+    value: f32,
+) {
+    write_input(positive, value.max(0.0));
+    write_input(negative, -value.min(0.0));
+}
+
+struct VirtualGamepad {
+    name: String,
+    connected: bool,
+    buttons: HashMap<Button, f32>,
+    axes: HashMap<Axis, f32>,
+    power: PowerInfo,
+}
+
+#[derive(Default)]
+struct VirtualGamepadsState {
+    gamepads: Vec<VirtualGamepad>,
+    events: Vec<GamepadEvent>,
+}
+
+/// Scriptable gamepads, driven by tests or bots instead of real devices.
+/// Handle is shared, so changes are visible to gamepad manager created from it.
+#[derive(Default, Clone)]
+pub struct VirtualGamepads {
+    state: Rc<RefCell<VirtualGamepadsState>>,
+}
+
+impl VirtualGamepads {
+    pub fn connect(&self, name: impl ToString) -> GamepadId {
+        let mut state = self.state.borrow_mut();
+        let id = GamepadId::Virtual(state.gamepads.len());
+        state.gamepads.push(VirtualGamepad {
+            name: name.to_string(),
+            connected: true,
+            buttons: Default::default(),
+            axes: Default::default(),
+            power: PowerInfo::Wired,
+        });
+        state.events.push(GamepadEvent::Connected(id));
+        id
+    }
+
+    /// Connects previously disconnected gamepad again, keeping its id.
+    pub fn reconnect(&self, id: GamepadId) -> bool {
+        self.set_connected(id, true)
+    }
+
+    pub fn disconnect(&self, id: GamepadId) -> bool {
+        self.set_connected(id, false)
+    }
+
+    pub fn press(&self, id: GamepadId, button: Button) {
+        self.set_button(id, button, 1.0);
+    }
+
+    pub fn release(&self, id: GamepadId, button: Button) {
+        self.set_button(id, button, 0.0);
+    }
+
+    pub fn set_button(&self, id: GamepadId, button: Button, value: f32) {
+        self.with_gamepad(id, |gamepad| {
+            gamepad.buttons.insert(button, value.clamp(0.0, 1.0));
+        });
+    }
+
+    pub fn set_axis(&self, id: GamepadId, axis: Axis, value: f32) {
+        self.with_gamepad(id, |gamepad| {
+            gamepad.axes.insert(axis, value.clamp(-1.0, 1.0));
+        });
+    }
+
+    pub fn set_power_info(&self, id: GamepadId, power: PowerInfo) {
+        self.with_gamepad(id, |gamepad| gamepad.power = power);
+    }
+
+    /// Releases all buttons and centers all axes.
+    pub fn reset(&self, id: GamepadId) {
+        self.with_gamepad(id, |gamepad| {
+            gamepad.buttons.clear();
+            gamepad.axes.clear();
+        });
+    }
+
+    fn set_connected(&self, id: GamepadId, connected: bool) -> bool {
+        let mut state = self.state.borrow_mut();
+        let GamepadId::Virtual(index) = id else {
+            return false;
+        };
+        let Some(gamepad) = state.gamepads.get_mut(index) else {
+            return false;
+        };
+        if gamepad.connected == connected {
+            return false;
+        }
+        gamepad.connected = connected;
+        if !connected {
+            gamepad.buttons.clear();
+            gamepad.axes.clear();
+        }
+        state.events.push(if connected {
+            GamepadEvent::Connected(id)
+        } else {
+            GamepadEvent::Disconnected(id)
+        });
+        true
+    }
+
+    fn with_gamepad(&self, id: GamepadId, f: impl FnOnce(&mut VirtualGamepad)) {
+        if let GamepadId::Virtual(index) = id {
+            if let Some(gamepad) = self.state.borrow_mut().gamepads.get_mut(index) {
+                f(gamepad);
+            }
+        }
+    }
+
+    fn gamepad<T>(&self, id: GamepadId, f: impl FnOnce(&VirtualGamepad) -> T) -> Option<T> {
+        let GamepadId::Virtual(index) = id else {
+            return None;
+        };
+        let state = self.state.borrow();
+        let gamepad = state.gamepads.get(index)?;
+        gamepad.connected.then(|| f(gamepad))
+    }
+}
+
+#[derive(Clone)]
+enum GamepadBackend {
+    Gilrs(Rc<RefCell<Gilrs>>),
+    Virtual(VirtualGamepads),
+}
+
+impl GamepadBackend {
+    fn gamepads(&self) -> Vec<GamepadId> {
+        match self {
+            Self::Gilrs(instance) => instance
+                .borrow()
+                .gamepads()
+                .map(|(id, _)| GamepadId::Device(id))
+                .collect(),
+            Self::Virtual(gamepads) => gamepads
+                .state
+                .borrow()
+                .gamepads
+                .iter()
+                .enumerate()
+                .filter(|(_, gamepad)| gamepad.connected)
+                .map(|(index, _)| GamepadId::Virtual(index))
+                .collect(),
+        }
+    }
+
+    fn is_connected(&self, id: GamepadId) -> bool {
+        match (self, id) {
+            (Self::Gilrs(instance), GamepadId::Device(id)) => {
+                instance.borrow().connected_gamepad(id).is_some()
+            }
+            (Self::Virtual(gamepads), id) => gamepads.gamepad(id, |_| ()).is_some(),
+            _ => false,
+        }
+    }
+
+    fn name(&self, id: GamepadId) -> Option<String> {
+        match (self, id) {
+            (Self::Gilrs(instance), GamepadId::Device(id)) => {
+                Some(instance.borrow().connected_gamepad(id)?.name().to_owned())
+            }
+            (Self::Virtual(gamepads), id) => gamepads.gamepad(id, |gamepad| gamepad.name.clone()),
+            _ => None,
+        }
+    }
+
+    fn vendor_id(&self, id: GamepadId) -> Option<u16> {
+        match (self, id) {
+            (Self::Gilrs(instance), GamepadId::Device(id)) => {
+                instance.borrow().connected_gamepad(id)?.vendor_id()
+            }
+            _ => None,
+        }
+    }
+
+    fn power_info(&self, id: GamepadId) -> Option<PowerInfo> {
+        match (self, id) {
+            (Self::Gilrs(instance), GamepadId::Device(id)) => {
+                Some(instance.borrow().connected_gamepad(id)?.power_info())
+            }
+            (Self::Virtual(gamepads), id) => gamepads.gamepad(id, |gamepad| gamepad.power),
+            _ => None,
+        }
+    }
+
+    /// Returns pressed state and value of button, if gamepad reported it.
+    fn button(&self, id: GamepadId, button: Button) -> Option<(bool, f32)> {
+        match (self, id) {
+            (Self::Gilrs(instance), GamepadId::Device(id)) => {
+                let instance = instance.borrow();
+                let gamepad = instance.connected_gamepad(id)?;
+                let data = gamepad.button_data(button)?;
+                Some((data.is_pressed(), data.value()))
+            }
+            (Self::Virtual(gamepads), id) => gamepads.gamepad(id, |gamepad| {
+                let value = gamepad.buttons.get(&button).copied().unwrap_or_default();
+                (value >= 0.5, value)
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns value of axis, if gamepad reported it.
+    fn axis(&self, id: GamepadId, axis: Axis) -> Option<f32> {
+        match (self, id) {
+            (Self::Gilrs(instance), GamepadId::Device(id)) => Some(
+                instance
+                    .borrow()
+                    .connected_gamepad(id)?
+                    .axis_data(axis)?
+                    .value(),
+            ),
+            (Self::Virtual(gamepads), id) => gamepads.gamepad(id, |gamepad| {
+                gamepad.axes.get(&axis).copied().unwrap_or_default()
+            }),
+            _ => None,
+        }
+    }
+
+    fn is_pressed(&self, id: GamepadId, button: Button) -> bool {
+        self.button(id, button)
+            .map(|(pressed, _)| pressed)
+            .unwrap_or_default()
+    }
+
+    /// Drains connection events reported since last call.
+    fn connection_events(&self) -> Vec<GamepadEvent> {
+        match self {
+            Self::Gilrs(instance) => {
+                let mut instance = instance.borrow_mut();
+                let mut result = Vec::new();
+                while let Some(event) = instance.next_event() {
+                    match event.event {
+                        EventType::Connected => {
+                            result.push(GamepadEvent::Connected(GamepadId::Device(event.id)));
+                        }
+                        EventType::Disconnected => {
+                            result.push(GamepadEvent::Disconnected(GamepadId::Device(event.id)));
+                        }
+                        _ => {}
+                    }
+                }
+                result
+            }
+            Self::Virtual(gamepads) => std::mem::take(&mut gamepads.state.borrow_mut().events),
         }
     }
 }
 
 #[derive(Clone)]
 pub struct GamepadInput {
-    instance: Rc<RefCell<Gilrs>>,
+    backend: GamepadBackend,
     used_gamepads: Rc<RefCell<HashSet<GamepadId>>>,
     id: Option<GamepadId>,
+    lost: Option<GamepadId>,
     pub buttons: HashMap<Button, InputActionOrAxisRef>,
     pub axes: HashMap<Axis, GamepadInputAxis>,
+    pub sticks: Vec<GamepadInputStick>,
     pub auto_acquire: bool,
 }
 
@@ -57,17 +772,42 @@ impl Drop for GamepadInput {
 }
 
 impl GamepadInput {
+    /// Acquires unused gamepad, preferring the one that was lost.
     pub fn acquire(&mut self) -> bool {
+        let lost = self.lost;
         self.release();
+        self.lost = lost;
         let mut used_gamepads = self.used_gamepads.borrow_mut();
-        for (id, _) in self.instance.borrow().gamepads() {
-            if !used_gamepads.contains(&id) {
-                used_gamepads.insert(id);
-                self.id = Some(id);
-                return true;
-            }
+        let id = lost
+            .filter(|id| !used_gamepads.contains(id) && self.backend.is_connected(*id))
+            .or_else(|| {
+                self.backend
+                    .gamepads()
+                    .into_iter()
+                    .find(|id| !used_gamepads.contains(id))
+            });
+        if let Some(id) = id {
+            used_gamepads.insert(id);
+            self.id = Some(id);
+            self.lost = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Acquires specific gamepad, if it is connected and not used by other input.
+    pub fn acquire_id(&mut self, id: GamepadId) -> bool {
+        if self.id == Some(id) {
+            return true;
+        }
+        let available = !self.used_gamepads.borrow().contains(&id) && self.backend.is_connected(id);
+        if available {
+            self.release();
+            self.used_gamepads.borrow_mut().insert(id);
+            self.id = Some(id);
         }
-        false
+        available
     }
 
     pub fn release(&mut self) {
@@ -75,6 +815,7 @@ impl GamepadInput {
             self.used_gamepads.borrow_mut().remove(id);
         }
         self.id = None;
+        self.lost = None;
     }
 
     pub fn id(&self) -> Option<GamepadId> {
@@ -85,6 +826,15 @@ impl GamepadInput {
         self.id.is_some()
     }
 
+    /// Tells if acquired gamepad got disconnected and was not yet reacquired or released.
+    pub fn is_lost(&self) -> bool {
+        self.lost.is_some()
+    }
+
+    pub fn lost_id(&self) -> Option<GamepadId> {
+        self.lost
+    }
+
     pub fn button(mut self, id: Button, input: impl Into<InputActionOrAxisRef>) -> Self {
         self.buttons.insert(id, input.into());
         self
@@ -95,6 +845,11 @@ impl GamepadInput {
         self
     }
 
+    pub fn stick(mut self, stick: GamepadInputStick) -> Self {
+        self.sticks.push(stick);
+        self
+    }
+
     pub fn auto_acquire(mut self) -> Self {
         self.auto_acquire = true;
         self
@@ -105,114 +860,421 @@ impl GamepadInput {
             self.acquire();
         }
         if let Some(id) = self.id {
-            let instance = self.instance.borrow();
-            let mut used_gamepads = self.used_gamepads.borrow_mut();
-            if let Some(gamepad) = instance.connected_gamepad(id) {
-                for (id, input) in &mut self.buttons {
-                    if let Some(data) = gamepad.button_data(*id) {
+            if self.backend.is_connected(id) {
+                for (button, input) in &mut self.buttons {
+                    if let Some((pressed, value)) = self.backend.button(id, *button) {
                         match input {
                             InputActionOrAxisRef::Action(input) => {
-                                input.set(input.get().change(data.is_pressed()));
+                                input.set(input.get().change(pressed));
                             }
                             InputActionOrAxisRef::Axis(input) => {
-                                input.set(InputAxis(data.value()));
+                                input.set(InputAxis(value));
                             }
                             _ => {}
                         }
                     }
                 }
-                for (id, input) in &mut self.axes {
-                    if let Some(data) = gamepad.axis_data(*id) {
+                for (axis, input) in &mut self.axes {
+                    if let Some(value) = self.backend.axis(id, *axis) {
                         match input {
-                            GamepadInputAxis::Single { input, deadzone } => {
-                                let mut value = data.value().abs();
-                                if value < *deadzone {
-                                    value = 0.0;
-                                }
-                                match input {
-                                    InputActionOrAxisRef::Action(input) => {
-                                        input.set(input.get().change(value > 0.5));
-                                    }
-                                    InputActionOrAxisRef::Axis(input) => {
-                                        input.set(InputAxis(value));
-                                    }
-                                    _ => {}
-                                }
+                            GamepadInputAxis::Single { input, response } => {
+                                write_input(input, response.apply_value(value).abs());
                             }
                             GamepadInputAxis::Double {
                                 negative,
                                 positive,
-                                deadzone,
+                                response,
                             } => {
-                                let mut value = data.value();
-                                if value.abs() < *deadzone {
-                                    value = 0.0;
-                                }
-                                match positive {
-                                    InputActionOrAxisRef::Action(input) => {
-                                        input.set(input.get().change(value > 0.5));
-                                    }
-                                    InputActionOrAxisRef::Axis(input) => {
-                                        input.set(InputAxis(value.max(0.0)));
-                                    }
-                                    _ => {}
-                                }
-                                match negative {
-                                    InputActionOrAxisRef::Action(input) => {
-                                        input.set(input.get().change(value < -0.5));
-                                    }
-                                    InputActionOrAxisRef::Axis(input) => {
-                                        input.set(InputAxis(-value.min(0.0)));
-                                    }
-                                    _ => {}
-                                }
+                                write_signed_input(negative, positive, response.apply_value(value));
                             }
                         }
                     }
                 }
+                for stick in &mut self.sticks {
+                    let value = stick.response.apply_stick(Vec2::new(
+                        self.backend.axis(id, stick.x).unwrap_or_default(),
+                        self.backend.axis(id, stick.y).unwrap_or_default(),
+                    ));
+                    write_signed_input(&mut stick.left, &mut stick.right, value.x);
+                    write_signed_input(&mut stick.down, &mut stick.up, value.y);
+                }
             } else {
-                used_gamepads.remove(&id);
+                self.used_gamepads.borrow_mut().remove(&id);
                 self.id = None;
+                self.lost = Some(id);
             }
         }
     }
 }
 
 pub struct GamepadManager {
-    instance: Option<Rc<RefCell<Gilrs>>>,
+    backend: Option<GamepadBackend>,
     used_gamepads: Rc<RefCell<HashSet<GamepadId>>>,
+    events: Vec<GamepadEvent>,
+    power: HashMap<GamepadId, PowerInfo>,
+    /// Revision of default mappings in use, for managers following defaults.
+    defaults_revision: Option<usize>,
 }
 
 impl Default for GamepadManager {
+    /// Manager following default mappings, reloading them on maintenance when they change.
     fn default() -> Self {
+        let revision = GamepadMappings::defaults_revision();
+        let mut result = Self::new_with_mappings(&GamepadMappings::defaults());
+        result.defaults_revision = Some(revision);
+        result
+    }
+}
+
+impl GamepadManager {
+    pub fn new_with_mappings(mappings: &GamepadMappings) -> Self {
+        Self::new(
+            Self::build_gilrs(mappings)
+                .map(|instance| GamepadBackend::Gilrs(Rc::new(RefCell::new(instance)))),
+        )
+    }
+
+    /// Creates manager that reads only virtual gamepads instead of real devices.
+    pub fn new_virtual(gamepads: VirtualGamepads) -> Self {
+        Self::new(Some(GamepadBackend::Virtual(gamepads)))
+    }
+
+    fn new(backend: Option<GamepadBackend>) -> Self {
         Self {
-            instance: Gilrs::new()
-                .ok()
-                .map(|instance| Rc::new(RefCell::new(instance))),
+            backend,
             used_gamepads: Default::default(),
+            events: Default::default(),
+            power: Default::default(),
+            defaults_revision: None,
+        }
+    }
+
+    fn build_gilrs(mappings: &GamepadMappings) -> Option<Gilrs> {
+        GilrsBuilder::new()
+            .add_mappings(&mappings.to_string())
+            .build()
+            .ok()
+    }
+
+    /// Rebuilds gamepad backend with given mappings. Gamepad inputs requested earlier
+    /// share the backend, so they read gamepads through new mappings too.
+    pub fn reload_mappings(&mut self, mappings: &GamepadMappings) {
+        if let Some(GamepadBackend::Gilrs(instance)) = self.backend.as_ref() {
+            if let Some(gilrs) = Self::build_gilrs(mappings) {
+                *instance.borrow_mut() = gilrs;
+            }
         }
     }
-}
 
-impl GamepadManager {
     pub fn is_supported(&self) -> bool {
-        self.instance.is_some()
+        self.backend.is_some()
+    }
+
+    pub fn virtual_gamepads(&self) -> Option<&VirtualGamepads> {
+        match self.backend.as_ref()? {
+            GamepadBackend::Virtual(gamepads) => Some(gamepads),
+            _ => None,
+        }
     }
 
     pub fn request_gamepad(&self) -> Option<GamepadInput> {
         Some(GamepadInput {
-            instance: self.instance.as_ref()?.clone(),
+            backend: self.backend.as_ref()?.clone(),
             used_gamepads: self.used_gamepads.clone(),
             id: Default::default(),
+            lost: Default::default(),
             buttons: Default::default(),
             axes: Default::default(),
+            sticks: Default::default(),
             auto_acquire: Default::default(),
         })
     }
 
+    /// Buttons and half-tilted axes currently held on any connected gamepad.
+    pub fn pressed_bindings(&self) -> impl Iterator<Item = InputBinding> {
+        self.connected_gamepads()
+            .into_iter()
+            .flat_map(|id| self.gamepad_pressed_bindings(id))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Buttons and half-tilted axes currently held on given gamepad.
+    pub fn gamepad_pressed_bindings(&self, id: GamepadId) -> Vec<InputBinding> {
+        let mut result = Vec::new();
+        if let Some(backend) = self.backend.as_ref() {
+            for button in GAMEPAD_BUTTONS {
+                if backend.is_pressed(id, button) {
+                    result.push(InputBinding::GamepadButton(button));
+                }
+            }
+            for axis in GAMEPAD_AXES {
+                let value = backend.axis(id, axis).unwrap_or_default();
+                if value.abs() > 0.5 {
+                    result.push(InputBinding::GamepadAxis {
+                        axis,
+                        positive: value > 0.0,
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    pub fn connected_gamepads(&self) -> Vec<GamepadId> {
+        self.backend
+            .as_ref()
+            .map(|backend| backend.gamepads())
+            .unwrap_or_default()
+    }
+
+    pub fn gamepad_name(&self, id: GamepadId) -> Option<String> {
+        self.backend.as_ref()?.name(id)
+    }
+
+    pub fn gamepad_style(&self, id: GamepadId) -> GamepadStyle {
+        let Some(backend) = self.backend.as_ref() else {
+            return Default::default();
+        };
+        GamepadStyle::detect(&backend.name(id).unwrap_or_default(), backend.vendor_id(id))
+    }
+
+    pub fn is_pressed(&self, id: GamepadId, button: Button) -> bool {
+        self.backend
+            .as_ref()
+            .map(|backend| backend.is_pressed(id, button))
+            .unwrap_or_default()
+    }
+
+    pub fn power_info(&self, id: GamepadId) -> Option<PowerInfo> {
+        self.power.get(&id).copied()
+    }
+
+    /// Events gathered during last maintenance.
+    pub fn events(&self) -> &[GamepadEvent] {
+        &self.events
+    }
+
+    /// Gathers connection events and reports power changes (including initial power state).
     pub fn maintain(&mut self) {
-        if let Some(instance) = self.instance.as_mut() {
-            while instance.borrow_mut().next_event().is_some() {}
+        self.events.clear();
+        if let Some(revision) = self.defaults_revision {
+            let current = GamepadMappings::defaults_revision();
+            if revision != current {
+                self.defaults_revision = Some(current);
+                self.reload_mappings(&GamepadMappings::defaults());
+            }
         }
+        if let Some(backend) = self.backend.as_ref() {
+            for event in backend.connection_events() {
+                if let GamepadEvent::Disconnected(id) = event {
+                    self.power.remove(&id);
+                }
+                self.events.push(event);
+            }
+            for id in backend.gamepads() {
+                let Some(power) = backend.power_info(id) else {
+                    continue;
+                };
+                if self.power.insert(id, power) != Some(power) {
+                    self.events.push(GamepadEvent::PowerChanged(id, power));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spitfire_input::InputActionRef;
+
+    #[test]
+    fn test_axis_response() {
+        let response = GamepadAxisResponse::new(0.2)
+            .outer_deadzone(0.1)
+            .deadzone_mode(GamepadDeadzoneMode::ScaledRadial);
+        assert_eq!(response.apply_stick(Vec2::new(0.1, 0.1)), Vec2::zero());
+        let diagonal = response.apply_stick(Vec2::new(0.5, 0.5));
+        assert!((diagonal.x - diagonal.y).abs() < 1.0e-6);
+        assert!(diagonal.x > 0.0 && diagonal.magnitude() < 0.75);
+        let full = response.apply_stick(Vec2::new(0.0, -0.95));
+        assert!((full.y + 1.0).abs() < 1.0e-6);
+
+        let axial = GamepadAxisResponse::new(0.2);
+        assert_eq!(axial.apply_stick(Vec2::new(0.1, 0.5)), Vec2::new(0.0, 0.5));
+        assert_eq!(axial.apply_value(-0.5), -0.5);
+
+        let exponential =
+            GamepadAxisResponse::new(0.0).curve(GamepadResponseCurve::Exponential(2.0));
+        assert!((exponential.apply_value(-0.5) + 0.25).abs() < 1.0e-6);
+        let sensitive = GamepadAxisResponse::new(0.0).sensitivity(2.0);
+        assert_eq!(sensitive.apply_value(0.75), 1.0);
+    }
+
+    #[test]
+    fn test_virtual_gamepad() {
+        let gamepads = VirtualGamepads::default();
+        let manager = GamepadManager::new_virtual(gamepads.clone());
+        let id = gamepads.connect("Bot");
+        let attack = InputActionRef::default();
+        let right = InputActionRef::default();
+        let mut input = manager
+            .request_gamepad()
+            .unwrap()
+            .button(Button::South, attack.clone())
+            .axis(
+                Axis::LeftStickX,
+                GamepadInputAxis::double(InputActionRef::default(), right.clone(), 0.2),
+            )
+            .auto_acquire();
+
+        gamepads.press(id, Button::South);
+        gamepads.set_axis(id, Axis::LeftStickX, 0.9);
+        input.apply();
+        assert_eq!(input.id(), Some(id));
+        assert!(attack.get().is_down());
+        assert!(right.get().is_down());
+        assert!(manager.is_pressed(id, Button::South));
+
+        gamepads.release(id, Button::South);
+        input.apply();
+        assert!(!attack.get().is_down());
+
+        gamepads.disconnect(id);
+        input.auto_acquire = false;
+        input.apply();
+        assert!(input.is_lost());
+        gamepads.reconnect(id);
+        assert!(input.acquire());
+        assert_eq!(input.id(), Some(id));
+    }
+
+    #[test]
+    fn test_gamepad_events() {
+        let gamepads = VirtualGamepads::default();
+        let mut manager = GamepadManager::new_virtual(gamepads.clone());
+        manager.maintain();
+        assert!(manager.events().is_empty());
+
+        let id = gamepads.connect("Bot");
+        manager.maintain();
+        assert_eq!(
+            manager.events(),
+            &[
+                GamepadEvent::Connected(id),
+                GamepadEvent::PowerChanged(id, PowerInfo::Wired)
+            ]
+        );
+        assert_eq!(manager.power_info(id), Some(PowerInfo::Wired));
+        manager.maintain();
+        assert!(manager.events().is_empty());
+
+        gamepads.set_power_info(id, PowerInfo::Discharging(50));
+        manager.maintain();
+        assert_eq!(
+            manager.events(),
+            &[GamepadEvent::PowerChanged(id, PowerInfo::Discharging(50))]
+        );
+
+        gamepads.disconnect(id);
+        manager.maintain();
+        assert_eq!(manager.events(), &[GamepadEvent::Disconnected(id)]);
+        assert_eq!(manager.power_info(id), None);
+
+        gamepads.reconnect(id);
+        manager.maintain();
+        assert_eq!(
+            manager.events(),
+            &[
+                GamepadEvent::Connected(id),
+                GamepadEvent::PowerChanged(id, PowerInfo::Discharging(50))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gamepad_lost_pause() {
+        let gamepads = VirtualGamepads::default();
+        let manager = GamepadManager::new_virtual(gamepads.clone());
+        let id = gamepads.connect("Bot");
+        let mut input = manager.request_gamepad().unwrap().auto_acquire();
+        input.apply();
+        assert_eq!(input.id(), Some(id));
+
+        // Game pauses while lost gamepad stays disconnected.
+        gamepads.disconnect(id);
+        input.apply();
+        assert!(input.is_lost());
+        input.apply();
+        assert!(input.is_lost());
+        assert_eq!(input.lost_id(), Some(id));
+
+        // Reconnecting resumes with the same gamepad.
+        gamepads.reconnect(id);
+        input.apply();
+        assert!(!input.is_lost());
+        assert_eq!(input.id(), Some(id));
+
+        // Releasing lost gamepad resumes without one.
+        gamepads.disconnect(id);
+        input.apply();
+        assert!(input.is_lost());
+        input.release();
+        assert!(!input.is_lost());
+        assert!(!input.is_connected());
+    }
+
+    #[test]
+    fn test_gamepad_mappings() {
+        let mut mappings = GamepadMappings::parse(
+            "# Comment\n\
+            03000000790000000600000000000000,Cheap Pad,a:b2,b:b1,platform:Linux,\n\
+            03000000790000000600000000000000,Cheap Pad,a:b2,b:b1,platform:Windows,\n",
+        )
+        .unwrap();
+        assert_eq!(mappings.len(), 2);
+        mappings
+            .add("03000000790000000600000000000000,Cheap Pad,a:b1,b:b2,platform:Linux,")
+            .unwrap();
+        assert_eq!(mappings.len(), 2);
+        assert!(mappings.lines()[1].contains("a:b1"));
+        assert!(mappings.add("nope,Cheap Pad,a:b1").is_err());
+        assert!(mappings
+            .add("03000000790000000600000000000000,,a:b1")
+            .is_err());
+        assert!(GamepadMappings::parse("03000000790000000600000000000000,Pad,a").is_err());
+    }
+
+    #[test]
+    fn test_default_gamepad_mappings() {
+        let shipped = GamepadMappings::parse(
+            "03000000790000000600000000000000,Cheap Pad,a:b2,b:b1,platform:Linux,\n\
+            03000000790000000600000000000001,Other Pad,a:b0,b:b1,platform:Linux,\n",
+        )
+        .unwrap();
+        let config = GamepadMappings::parse(
+            "03000000790000000600000000000000,Cheap Pad,a:b1,b:b2,platform:Linux,",
+        )
+        .unwrap();
+        let revision = GamepadMappings::defaults_revision();
+        config.register_config();
+        shipped.register_default();
+        assert!(GamepadMappings::defaults_revision() > revision);
+        let defaults = GamepadMappings::defaults();
+        assert_eq!(defaults.len(), 2);
+        let line = defaults
+            .lines()
+            .iter()
+            .find(|line| line.contains("Cheap Pad"))
+            .unwrap();
+        assert!(line.contains("a:b1"));
+
+        GamepadMappings::default().register_config();
+        let defaults = GamepadMappings::defaults();
+        assert!(defaults
+            .lines()
+            .iter()
+            .any(|line| line.contains("Cheap Pad,a:b2")));
     }
 }
@@ -216,6 +216,21 @@ impl GridWorld {
         Vec2::new(location.x as f32, location.y as f32) * self.tile_size + self.position
             - Vec2::new(size.x as f32, size.y as f32) * self.tile_size * self.pivot
     }
+
+    /// Tests whether any collider lies between two world points, excluding their own tiles.
+    pub fn is_line_blocked(&self, from: Vec2<f32>, to: Vec2<f32>) -> bool {
+        let step = self.tile_size.reduce_partial_min().max(f32::EPSILON) * 0.5;
+        let count = (from.distance(to) / step).ceil() as usize;
+        let from_local = self.world_to_local(from);
+        let to_local = self.world_to_local(to);
+        (1..count).any(|index| {
+            let location = self.world_to_local(Vec2::lerp(from, to, index as f32 / count as f32));
+            location
+                .filter(|location| Some(*location) != from_local && Some(*location) != to_local)
+                .map(|location| self.collider(location))
+                .unwrap_or_default()
+        })
+    }
 }
 
 impl Drawable for GridWorld {
@@ -269,3 +284,22 @@ impl Drawable for GridWorld {
             .draw(context, graphics);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_world_line_blocked() {
+        let world = GridWorld::new(
+            10.0.into(),
+            Default::default(),
+            GridWorldLayer::new(TileMap::new(5.into(), 0)),
+        )
+        .with_collider(Vec2::new(2, 2));
+        assert!(world.is_line_blocked(Vec2::new(5.0, 25.0), Vec2::new(45.0, 25.0)));
+        assert!(!world.is_line_blocked(Vec2::new(5.0, 5.0), Vec2::new(45.0, 5.0)));
+        assert!(!world.is_line_blocked(Vec2::new(25.0, 25.0), Vec2::new(45.0, 25.0)));
+        assert!(!world.is_line_blocked(Vec2::new(5.0, 25.0), Vec2::new(25.0, 25.0)));
+    }
+}
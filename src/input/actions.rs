@@ -0,0 +1,442 @@
+use crate::{
+    gamepad::{
+        GamepadAxisResponse, GamepadDeadzoneMode, GamepadInput, GamepadInputAxis, GamepadInputStick,
+    },
+    third_party::windowing::event::{MouseButton, VirtualKeyCode},
+};
+use gilrs::{Axis, Button};
+use serde::{Deserialize, Serialize};
+use spitfire_input::{
+    InputActionOrAxisRef, InputActionRef, InputContext, InputMapping, VirtualAction,
+};
+use std::collections::{BTreeMap, HashMap};
+use typid::ID;
+
+/// Bindings of named actions, as stored in config.
+pub type InputBindings = BTreeMap<String, Vec<InputBinding>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputDevice {
+    KeyboardMouse,
+    Gamepad,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputBinding {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    GamepadButton(Button),
+    GamepadAxis { axis: Axis, positive: bool },
+}
+
+impl InputBinding {
+    pub fn axis_negative(axis: Axis) -> Self {
+        Self::GamepadAxis {
+            axis,
+            positive: false,
+        }
+    }
+
+    pub fn axis_positive(axis: Axis) -> Self {
+        Self::GamepadAxis {
+            axis,
+            positive: true,
+        }
+    }
+
+    pub fn device(&self) -> InputDevice {
+        match self {
+            Self::Key(_) | Self::Mouse(_) => InputDevice::KeyboardMouse,
+            Self::GamepadButton(_) | Self::GamepadAxis { .. } => InputDevice::Gamepad,
+        }
+    }
+
+    /// Short human readable name, usable in UI.
+    pub fn label(&self) -> String {
+        match self {
+            Self::Key(key) => format!("{:?}", key),
+            Self::Mouse(MouseButton::Other(index)) => format!("Mouse {}", index),
+            Self::Mouse(button) => format!("Mouse {:?}", button),
+            Self::GamepadButton(button) => format!("{:?}", button),
+            Self::GamepadAxis { axis, positive } => {
+                format!("{:?}{}", axis, if *positive { "+" } else { "-" })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputRebindResult {
+    Rebound,
+    /// Binding is already used by listed actions, nothing was changed.
+    Conflict(Vec<String>),
+    UnknownAction,
+}
+
+#[derive(Default, Clone)]
+pub struct InputActionEntry {
+    pub input: InputActionRef,
+    pub bindings: Vec<InputBinding>,
+    pub defaults: Vec<InputBinding>,
+}
+
+/// Named actions with rebindable keyboard, mouse and gamepad bindings.
+/// Bound X and Y axes of the same stick are processed together as stick.
+pub struct InputActionMap {
+    pub axis_response: GamepadAxisResponse,
+    pub stick_response: GamepadAxisResponse,
+    /// Overrides of response per axis, where stick uses override of its X axis.
+    pub axis_responses: HashMap<Axis, GamepadAxisResponse>,
+    actions: BTreeMap<String, InputActionEntry>,
+    gamepad: Option<GamepadInput>,
+    mapping: Option<ID<InputMapping>>,
+    dirty: bool,
+}
+
+impl Default for InputActionMap {
+    fn default() -> Self {
+        Self {
+            axis_response: GamepadAxisResponse::new(0.1),
+            stick_response: GamepadAxisResponse::new(0.15)
+                .deadzone_mode(GamepadDeadzoneMode::ScaledRadial),
+            axis_responses: Default::default(),
+            actions: Default::default(),
+            gamepad: None,
+            mapping: None,
+            dirty: false,
+        }
+    }
+}
+
+impl InputActionMap {
+    pub fn with_action(
+        mut self,
+        name: impl ToString,
+        defaults: impl IntoIterator<Item = InputBinding>,
+    ) -> Self {
+        let defaults = defaults.into_iter().collect::<Vec<_>>();
+        self.actions.insert(
+            name.to_string(),
+            InputActionEntry {
+                input: Default::default(),
+                bindings: defaults.clone(),
+                defaults,
+            },
+        );
+        self.dirty = true;
+        self
+    }
+
+    pub fn with_gamepad(mut self, gamepad: Option<GamepadInput>) -> Self {
+        self.gamepad = gamepad;
+        self.dirty = true;
+        self
+    }
+
+    pub fn with_axis_response(mut self, axis: Axis, response: GamepadAxisResponse) -> Self {
+        self.axis_responses.insert(axis, response);
+        self.dirty = true;
+        self
+    }
+
+    pub fn with_bindings(mut self, bindings: &InputBindings) -> Self {
+        self.apply_bindings(bindings);
+        self
+    }
+
+    pub fn gamepad(&self) -> Option<&GamepadInput> {
+        self.gamepad.as_ref()
+    }
+
+    pub fn gamepad_mut(&mut self) -> Option<&mut GamepadInput> {
+        self.gamepad.as_mut()
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = (&str, &InputActionEntry)> {
+        self.actions
+            .iter()
+            .map(|(name, entry)| (name.as_str(), entry))
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&InputActionEntry> {
+        self.actions.get(name)
+    }
+
+    /// Action state reference, or dummy one when action is not registered.
+    pub fn action(&self, name: &str) -> InputActionRef {
+        self.actions
+            .get(name)
+            .map(|entry| entry.input.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn bindings(&self, name: &str) -> &[InputBinding] {
+        self.actions
+            .get(name)
+            .map(|entry| entry.bindings.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn binding_for(&self, name: &str, device: InputDevice) -> Option<InputBinding> {
+        self.bindings(name)
+            .iter()
+            .find(|binding| binding.device() == device)
+            .copied()
+    }
+
+    /// Names of actions other than `except` that use given binding.
+    pub fn conflicts(&self, binding: InputBinding, except: &str) -> Vec<String> {
+        self.actions
+            .iter()
+            .filter(|(name, entry)| *name != except && entry.bindings.contains(&binding))
+            .map(|(name, _)| name.to_owned())
+            .collect()
+    }
+
+    /// Replaces action binding of the same device as new binding.
+    pub fn rebind(&mut self, name: &str, binding: InputBinding) -> InputRebindResult {
+        if !self.actions.contains_key(name) {
+            return InputRebindResult::UnknownAction;
+        }
+        let conflicts = self.conflicts(binding, name);
+        if !conflicts.is_empty() {
+            return InputRebindResult::Conflict(conflicts);
+        }
+        self.force_rebind(name, binding)
+    }
+
+    /// Rebinds action, removing binding from any other action that used it.
+    pub fn force_rebind(&mut self, name: &str, binding: InputBinding) -> InputRebindResult {
+        if !self.actions.contains_key(name) {
+            return InputRebindResult::UnknownAction;
+        }
+        for (other, entry) in &mut self.actions {
+            if other != name {
+                entry.bindings.retain(|item| *item != binding);
+            }
+        }
+        let entry = self.actions.get_mut(name).unwrap();
+        if entry.bindings.contains(&binding) {
+            return InputRebindResult::Rebound;
+        }
+        if let Some(item) = entry
+            .bindings
+            .iter_mut()
+            .find(|item| item.device() == binding.device())
+        {
+            *item = binding;
+        } else {
+            entry.bindings.push(binding);
+        }
+        self.dirty = true;
+        InputRebindResult::Rebound
+    }
+
+    pub fn unbind(&mut self, name: &str, binding: InputBinding) {
+        if let Some(entry) = self.actions.get_mut(name) {
+            entry.bindings.retain(|item| *item != binding);
+            self.dirty = true;
+        }
+    }
+
+    pub fn reset(&mut self, name: &str) {
+        if let Some(entry) = self.actions.get_mut(name) {
+            entry.bindings = entry.defaults.clone();
+            self.dirty = true;
+        }
+    }
+
+    pub fn reset_all(&mut self) {
+        for entry in self.actions.values_mut() {
+            entry.bindings = entry.defaults.clone();
+        }
+        self.dirty = true;
+    }
+
+    /// Current bindings of all actions, ready to be stored in config.
+    pub fn to_bindings(&self) -> InputBindings {
+        self.actions
+            .iter()
+            .map(|(name, entry)| (name.to_owned(), entry.bindings.clone()))
+            .collect()
+    }
+
+    /// Applies stored bindings to registered actions, ignoring unknown ones.
+    pub fn apply_bindings(&mut self, bindings: &InputBindings) {
+        for (name, bindings) in bindings {
+            if let Some(entry) = self.actions.get_mut(name) {
+                entry.bindings = bindings.to_owned();
+            }
+        }
+        self.dirty = true;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.mapping.is_some()
+    }
+
+    pub fn activate(&mut self, input: &mut InputContext) {
+        if self.mapping.is_none() {
+            self.mapping = Some(input.push_mapping(self.mapping()));
+        }
+        self.apply_gamepad_bindings();
+        self.dirty = false;
+    }
+
+    pub fn deactivate(&mut self, input: &mut InputContext) {
+        if let Some(id) = self.mapping.take() {
+            input.remove_mapping(id);
+        }
+    }
+
+    /// Refreshes changed bindings and reads gamepad state.
+    pub fn maintain(&mut self, input: &mut InputContext) {
+        if self.dirty && self.is_active() {
+            self.deactivate(input);
+            self.activate(input);
+        }
+        if let Some(gamepad) = self.gamepad.as_mut() {
+            gamepad.apply();
+        }
+    }
+
+    /// Builds keyboard and mouse mapping of current bindings.
+    pub fn mapping(&self) -> InputMapping {
+        let mut result = InputMapping::default();
+        for entry in self.actions.values() {
+            for binding in &entry.bindings {
+                match binding {
+                    InputBinding::Key(key) => {
+                        result = result.action(VirtualAction::KeyButton(*key), entry.input.clone());
+                    }
+                    InputBinding::Mouse(button) => {
+                        result =
+                            result.action(VirtualAction::MouseButton(*button), entry.input.clone());
+                    }
+                    _ => {}
+                }
+            }
+        }
+        result
+    }
+
+    fn apply_gamepad_bindings(&mut self) {
+        let Some(gamepad) = self.gamepad.as_mut() else {
+            return;
+        };
+        gamepad.buttons.clear();
+        gamepad.axes.clear();
+        gamepad.sticks.clear();
+        let mut axes = HashMap::<Axis, (InputActionOrAxisRef, InputActionOrAxisRef)>::new();
+        for entry in self.actions.values() {
+            for binding in &entry.bindings {
+                match *binding {
+                    InputBinding::GamepadButton(button) => {
+                        gamepad.buttons.insert(button, entry.input.clone().into());
+                    }
+                    InputBinding::GamepadAxis { axis, positive } => {
+                        let (negative_input, positive_input) =
+                            axes.entry(axis).or_insert_with(|| {
+                                (
+                                    InputActionRef::default().into(),
+                                    InputActionRef::default().into(),
+                                )
+                            });
+                        if positive {
+                            *positive_input = entry.input.clone().into();
+                        } else {
+                            *negative_input = entry.input.clone().into();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for (x, y) in [
+            (Axis::LeftStickX, Axis::LeftStickY),
+            (Axis::RightStickX, Axis::RightStickY),
+        ] {
+            if axes.contains_key(&x) && axes.contains_key(&y) {
+                let (left, right) = axes.remove(&x).unwrap();
+                let (down, up) = axes.remove(&y).unwrap();
+                gamepad.sticks.push(
+                    GamepadInputStick::new(x, y, left, right, down, up).response(
+                        self.axis_responses
+                            .get(&x)
+                            .unwrap_or(&self.stick_response)
+                            .clone(),
+                    ),
+                );
+            }
+        }
+        for (axis, (negative, positive)) in axes {
+            gamepad.axes.insert(
+                axis,
+                GamepadInputAxis::Double {
+                    negative,
+                    positive,
+                    response: self
+                        .axis_responses
+                        .get(&axis)
+                        .unwrap_or(&self.axis_response)
+                        .clone(),
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_rebinding() {
+        let mut actions = InputActionMap::default()
+            .with_action(
+                "attack",
+                [
+                    InputBinding::Key(VirtualKeyCode::Space),
+                    InputBinding::GamepadButton(Button::South),
+                ],
+            )
+            .with_action("weapon_next", [InputBinding::Key(VirtualKeyCode::E)]);
+
+        assert_eq!(
+            actions.rebind("attack", InputBinding::Key(VirtualKeyCode::E)),
+            InputRebindResult::Conflict(vec!["weapon_next".to_owned()])
+        );
+        assert_eq!(
+            actions.rebind("attack", InputBinding::Key(VirtualKeyCode::F)),
+            InputRebindResult::Rebound
+        );
+        assert_eq!(
+            actions.bindings("attack"),
+            &[
+                InputBinding::Key(VirtualKeyCode::F),
+                InputBinding::GamepadButton(Button::South),
+            ]
+        );
+        assert_eq!(
+            actions.force_rebind("attack", InputBinding::Key(VirtualKeyCode::E)),
+            InputRebindResult::Rebound
+        );
+        assert!(actions.bindings("weapon_next").is_empty());
+        assert_eq!(
+            actions.rebind("missing", InputBinding::Key(VirtualKeyCode::E)),
+            InputRebindResult::UnknownAction
+        );
+
+        let bindings = actions.to_bindings();
+        let content = toml::to_string(&bindings).unwrap();
+        let loaded = toml::from_str::<InputBindings>(&content).unwrap();
+        assert_eq!(loaded, bindings);
+
+        actions.reset_all();
+        assert_eq!(
+            actions.bindings("weapon_next"),
+            &[InputBinding::Key(VirtualKeyCode::E)]
+        );
+    }
+}
@@ -0,0 +1,362 @@
+use spitfire_input::{CardinalInputCombinator, InputActionRef};
+use std::collections::VecDeque;
+
+/// 8-way direction, with Y axis pointing down like in `CardinalInputCombinator`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputDirection {
+    #[default]
+    Neutral,
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl InputDirection {
+    pub fn from_vector(x: f32, y: f32, threshold: f32) -> Self {
+        let horizontal = if x > threshold {
+            1
+        } else if x < -threshold {
+            -1
+        } else {
+            0
+        };
+        let vertical = if y > threshold {
+            1
+        } else if y < -threshold {
+            -1
+        } else {
+            0
+        };
+        match (horizontal, vertical) {
+            (0, -1) => Self::Up,
+            (1, -1) => Self::UpRight,
+            (1, 0) => Self::Right,
+            (1, 1) => Self::DownRight,
+            (0, 1) => Self::Down,
+            (-1, 1) => Self::DownLeft,
+            (-1, 0) => Self::Left,
+            (-1, -1) => Self::UpLeft,
+            _ => Self::Neutral,
+        }
+    }
+
+    /// Swaps left and right, so "forward" sequences work for both facings.
+    pub fn mirrored(self) -> Self {
+        match self {
+            Self::UpRight => Self::UpLeft,
+            Self::Right => Self::Left,
+            Self::DownRight => Self::DownLeft,
+            Self::DownLeft => Self::DownRight,
+            Self::Left => Self::Right,
+            Self::UpLeft => Self::UpRight,
+            direction => direction,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InputToken {
+    Action(String),
+    Direction(InputDirection),
+}
+
+impl InputToken {
+    pub fn action(name: impl ToString) -> Self {
+        Self::Action(name.to_string())
+    }
+
+    pub fn mirrored(&self) -> Self {
+        match self {
+            Self::Action(name) => Self::Action(name.to_owned()),
+            Self::Direction(direction) => Self::Direction(direction.mirrored()),
+        }
+    }
+}
+
+/// Ordered inputs that have to be entered with limited time between each step.
+/// Other inputs in between steps are tolerated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputSequence {
+    pub steps: Vec<InputToken>,
+    /// Max seconds between consecutive steps.
+    pub step_window: f32,
+}
+
+impl InputSequence {
+    pub fn new(step_window: f32) -> Self {
+        Self {
+            steps: Default::default(),
+            step_window,
+        }
+    }
+
+    pub fn action(mut self, name: impl ToString) -> Self {
+        self.steps.push(InputToken::action(name));
+        self
+    }
+
+    pub fn direction(mut self, direction: InputDirection) -> Self {
+        self.steps.push(InputToken::Direction(direction));
+        self
+    }
+
+    pub fn mirrored(&self) -> Self {
+        Self {
+            steps: self.steps.iter().map(|step| step.mirrored()).collect(),
+            step_window: self.step_window,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct BufferedInput {
+    token: InputToken,
+    time: f32,
+    consumed: bool,
+}
+
+/// Records action presses and direction changes, so they can be consumed some time
+/// after they happened instead of requiring frame-perfect timing.
+pub struct InputBuffer {
+    /// Seconds during which press stays buffered.
+    pub window: f32,
+    /// Seconds after which entries get forgotten, should cover longest sequence.
+    pub history: f32,
+    pub direction_threshold: f32,
+    actions: Vec<(String, InputActionRef)>,
+    directions: Option<CardinalInputCombinator>,
+    direction: InputDirection,
+    entries: VecDeque<BufferedInput>,
+    time: f32,
+}
+
+impl Default for InputBuffer {
+    fn default() -> Self {
+        Self::new(0.15)
+    }
+}
+
+impl InputBuffer {
+    pub fn new(window: f32) -> Self {
+        Self {
+            window,
+            history: 1.0,
+            direction_threshold: 0.5,
+            actions: Default::default(),
+            directions: None,
+            direction: Default::default(),
+            entries: Default::default(),
+            time: 0.0,
+        }
+    }
+
+    pub fn with_action(mut self, name: impl ToString, input: InputActionRef) -> Self {
+        self.actions.push((name.to_string(), input));
+        self
+    }
+
+    pub fn with_directions(mut self, input: CardinalInputCombinator) -> Self {
+        self.directions = Some(input);
+        self
+    }
+
+    pub fn with_history(mut self, seconds: f32) -> Self {
+        self.history = seconds;
+        self
+    }
+
+    pub fn with_direction_threshold(mut self, value: f32) -> Self {
+        self.direction_threshold = value;
+        self
+    }
+
+    pub fn direction(&self) -> InputDirection {
+        self.direction
+    }
+
+    /// Records presses of this frame and forgets old entries.
+    pub fn update(&mut self, delta_time: f32) {
+        self.time += delta_time;
+        let pressed = self
+            .actions
+            .iter()
+            .filter(|(_, input)| input.get().is_pressed())
+            .map(|(name, _)| InputToken::Action(name.to_owned()))
+            .collect::<Vec<_>>();
+        for token in pressed {
+            self.push(token);
+        }
+        if let Some(directions) = self.directions.as_ref() {
+            let [x, y] = directions.get();
+            let direction = InputDirection::from_vector(x, y, self.direction_threshold);
+            if direction != self.direction {
+                self.direction = direction;
+                self.push(InputToken::Direction(direction));
+            }
+        }
+        let limit = self.time - self.history.max(self.window);
+        while self
+            .entries
+            .front()
+            .map(|entry| entry.time < limit)
+            .unwrap_or_default()
+        {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn push(&mut self, token: InputToken) {
+        self.entries.push_back(BufferedInput {
+            token,
+            time: self.time,
+            consumed: false,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn is_buffered(&self, name: &str) -> bool {
+        self.find_action(name).is_some()
+    }
+
+    /// Consumes latest buffered press of action.
+    pub fn consume(&mut self, name: &str) -> bool {
+        if let Some(index) = self.find_action(name) {
+            self.entries[index].consumed = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Tells if sequence was entered, with its last step still buffered.
+    pub fn matches(&self, sequence: &InputSequence) -> bool {
+        self.find_sequence(sequence).is_some()
+    }
+
+    /// Consumes all entries matched by sequence, so it does not trigger again.
+    pub fn consume_sequence(&mut self, sequence: &InputSequence) -> bool {
+        if let Some(indices) = self.find_sequence(sequence) {
+            for index in indices {
+                self.entries[index].consumed = true;
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn find_action(&self, name: &str) -> Option<usize> {
+        let limit = self.time - self.window;
+        self.entries
+            .iter()
+            .enumerate()
+            .rev()
+            .take_while(|(_, entry)| entry.time >= limit)
+            .find(|(_, entry)| {
+                !entry.consumed
+                    && matches!(&entry.token, InputToken::Action(action) if action == name)
+            })
+            .map(|(index, _)| index)
+    }
+
+    fn find_sequence(&self, sequence: &InputSequence) -> Option<Vec<usize>> {
+        let mut result = Vec::with_capacity(sequence.steps.len());
+        if self.find_steps(
+            &sequence.steps,
+            sequence.step_window,
+            self.entries.len(),
+            self.time - self.window,
+            &mut result,
+        ) {
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    /// Matches steps backwards, trying latest candidates first.
+    fn find_steps(
+        &self,
+        steps: &[InputToken],
+        step_window: f32,
+        before: usize,
+        limit: f32,
+        result: &mut Vec<usize>,
+    ) -> bool {
+        let Some((step, rest)) = steps.split_last() else {
+            return true;
+        };
+        for index in (0..before).rev() {
+            let entry = &self.entries[index];
+            if entry.time < limit {
+                break;
+            }
+            if entry.consumed || &entry.token != step {
+                continue;
+            }
+            result.push(index);
+            if self.find_steps(rest, step_window, index, entry.time - step_window, result) {
+                return true;
+            }
+            result.pop();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_buffer() {
+        let mut buffer = InputBuffer::new(0.2);
+        buffer.push(InputToken::action("attack"));
+        buffer.update(0.1);
+        assert!(buffer.is_buffered("attack"));
+        assert!(buffer.consume("attack"));
+        assert!(!buffer.is_buffered("attack"));
+        buffer.push(InputToken::action("attack"));
+        buffer.update(0.3);
+        assert!(!buffer.consume("attack"));
+
+        let fireball = InputSequence::new(0.15)
+            .direction(InputDirection::Down)
+            .direction(InputDirection::DownRight)
+            .direction(InputDirection::Right)
+            .action("attack");
+        for token in [
+            InputToken::Direction(InputDirection::Down),
+            InputToken::Direction(InputDirection::DownRight),
+            InputToken::action("jump"),
+            InputToken::Direction(InputDirection::Right),
+            InputToken::action("attack"),
+        ] {
+            buffer.push(token);
+            buffer.update(0.05);
+        }
+        assert!(buffer.matches(&fireball));
+        assert!(!buffer.matches(&fireball.mirrored()));
+        assert!(buffer.consume_sequence(&fireball));
+        assert!(!buffer.matches(&fireball));
+
+        for token in [
+            InputToken::Direction(InputDirection::Down),
+            InputToken::Direction(InputDirection::DownRight),
+            InputToken::Direction(InputDirection::Right),
+        ] {
+            buffer.push(token);
+            buffer.update(0.2);
+        }
+        buffer.push(InputToken::action("attack"));
+        assert!(!buffer.matches(&fireball));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{
+    gamepad::GamepadManager,
+    input::actions::InputBinding,
+    third_party::windowing::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent},
+};
+use std::collections::HashSet;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputCaptureState {
+    #[default]
+    Idle,
+    Listening,
+    Captured(InputBinding),
+    Cancelled,
+}
+
+/// "Press a key to rebind" listener, fed with window events by game instance.
+/// While listening it consumes keyboard and mouse presses, so they do not trigger actions.
+pub struct InputCapture {
+    pub cancel_key: Option<VirtualKeyCode>,
+    state: InputCaptureState,
+    pressed: HashSet<InputBinding>,
+    gamepad_pressed: Option<HashSet<InputBinding>>,
+}
+
+impl Default for InputCapture {
+    fn default() -> Self {
+        Self {
+            cancel_key: Some(VirtualKeyCode::Escape),
+            state: Default::default(),
+            pressed: Default::default(),
+            gamepad_pressed: None,
+        }
+    }
+}
+
+impl InputCapture {
+    pub fn state(&self) -> InputCaptureState {
+        self.state
+    }
+
+    pub fn is_listening(&self) -> bool {
+        self.state == InputCaptureState::Listening
+    }
+
+    pub fn start(&mut self) {
+        self.state = InputCaptureState::Listening;
+        self.gamepad_pressed = None;
+    }
+
+    pub fn cancel(&mut self) {
+        if self.is_listening() {
+            self.state = InputCaptureState::Cancelled;
+        }
+    }
+
+    /// Returns captured or cancelled result and goes back to idle.
+    pub fn take(&mut self) -> Option<InputCaptureState> {
+        match self.state {
+            InputCaptureState::Captured(_) | InputCaptureState::Cancelled => {
+                Some(std::mem::take(&mut self.state))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if event got consumed by capture.
+    pub fn on_event(&mut self, event: &WindowEvent) -> bool {
+        let (binding, pressed) = match event {
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => (InputBinding::Key(*key), *state == ElementState::Pressed),
+            WindowEvent::MouseInput { state, button, .. } => (
+                InputBinding::Mouse(*button),
+                *state == ElementState::Pressed,
+            ),
+            _ => return false,
+        };
+        if !pressed {
+            self.pressed.remove(&binding);
+            return false;
+        }
+        // Ignore key repeats and buttons held since before listening started.
+        if !self.pressed.insert(binding) || !self.is_listening() {
+            return false;
+        }
+        self.state = match (binding, self.cancel_key) {
+            (InputBinding::Key(key), Some(cancel)) if key == cancel => InputCaptureState::Cancelled,
+            _ => InputCaptureState::Captured(binding),
+        };
+        true
+    }
+
+    /// Captures gamepad button or axis pressed after listening has started.
+    pub fn poll_gamepads(&mut self, gamepads: &GamepadManager) {
+        if !self.is_listening() {
+            return;
+        }
+        let current = gamepads.pressed_bindings().collect::<HashSet<_>>();
+        if let Some(previous) = self.gamepad_pressed.as_ref() {
+            if let Some(binding) = current.difference(previous).next() {
+                self.state = InputCaptureState::Captured(*binding);
+            }
+        }
+        self.gamepad_pressed = Some(current);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{
+    input::actions::InputBinding,
+    third_party::windowing::{
+        dpi::PhysicalPosition,
+        event::{
+            DeviceId, ElementState, KeyboardInput, MouseButton, MouseScrollDelta, TouchPhase,
+            VirtualKeyCode, WindowEvent,
+        },
+    },
+};
+use spitfire_input::InputContext;
+
+/// Feeds synthetic window events into input context, so tests and bots can drive
+/// mapped actions and axes without real window.
+/// Pressed and released edges last until next `InputContext::maintain` call.
+pub trait InputInjector {
+    fn inject_event(&mut self, event: &WindowEvent);
+
+    fn inject_key(&mut self, key: VirtualKeyCode, pressed: bool) {
+        self.inject_event(&key_event(key, pressed));
+    }
+
+    fn inject_mouse_button(&mut self, button: MouseButton, pressed: bool) {
+        self.inject_event(&mouse_button_event(button, pressed));
+    }
+
+    fn inject_mouse_position(&mut self, x: f64, y: f64) {
+        self.inject_event(&mouse_position_event(x, y));
+    }
+
+    fn inject_mouse_wheel(&mut self, x: f32, y: f32) {
+        self.inject_event(&mouse_wheel_event(x, y));
+    }
+
+    /// Injects keyboard or mouse binding, returns false for gamepad bindings,
+    /// which should be scripted with `VirtualGamepads` instead.
+    fn inject_binding(&mut self, binding: InputBinding, pressed: bool) -> bool {
+        match binding {
+            InputBinding::Key(key) => self.inject_key(key, pressed),
+            InputBinding::Mouse(button) => self.inject_mouse_button(button, pressed),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl InputInjector for InputContext {
+    fn inject_event(&mut self, event: &WindowEvent) {
+        self.on_event(event);
+    }
+}
+
+fn device_id() -> DeviceId {
+    // Dummy device id is only meant for synthetic events like these.
+    unsafe { DeviceId::dummy() }
+}
+
+fn element_state(pressed: bool) -> ElementState {
+    if pressed {
+        ElementState::Pressed
+    } else {
+        ElementState::Released
+    }
+}
+
+#[allow(deprecated)]
+pub fn key_event(key: VirtualKeyCode, pressed: bool) -> WindowEvent<'static> {
+    WindowEvent::KeyboardInput {
+        device_id: device_id(),
+        input: KeyboardInput {
+            scancode: 0,
+            state: element_state(pressed),
+            virtual_keycode: Some(key),
+            modifiers: Default::default(),
+        },
+        is_synthetic: true,
+    }
+}
+
+#[allow(deprecated)]
+pub fn mouse_button_event(button: MouseButton, pressed: bool) -> WindowEvent<'static> {
+    WindowEvent::MouseInput {
+        device_id: device_id(),
+        state: element_state(pressed),
+        button,
+        modifiers: Default::default(),
+    }
+}
+
+#[allow(deprecated)]
+pub fn mouse_position_event(x: f64, y: f64) -> WindowEvent<'static> {
+    WindowEvent::CursorMoved {
+        device_id: device_id(),
+        position: PhysicalPosition::new(x, y),
+        modifiers: Default::default(),
+    }
+}
+
+#[allow(deprecated)]
+pub fn mouse_wheel_event(x: f32, y: f32) -> WindowEvent<'static> {
+    WindowEvent::MouseWheel {
+        device_id: device_id(),
+        delta: MouseScrollDelta::LineDelta(x, y),
+        phase: TouchPhase::Moved,
+        modifiers: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gamepad::{GamepadManager, VirtualGamepads},
+        input::actions::InputActionMap,
+    };
+    use gilrs::Button;
+
+    #[test]
+    fn test_input_injection() {
+        let gamepads = VirtualGamepads::default();
+        let manager = GamepadManager::new_virtual(gamepads.clone());
+        let id = gamepads.connect("Bot");
+        let mut input = InputContext::default();
+        let mut actions = InputActionMap::default()
+            .with_action(
+                "attack",
+                [
+                    InputBinding::Key(VirtualKeyCode::Space),
+                    InputBinding::GamepadButton(Button::South),
+                ],
+            )
+            .with_gamepad(
+                manager
+                    .request_gamepad()
+                    .map(|gamepad| gamepad.auto_acquire()),
+            );
+        let attack = actions.action("attack");
+        actions.activate(&mut input);
+
+        input.inject_key(VirtualKeyCode::Space, true);
+        assert!(attack.get().is_pressed());
+        input.maintain();
+        input.inject_key(VirtualKeyCode::Space, false);
+        assert!(!attack.get().is_down());
+        input.maintain();
+
+        gamepads.press(id, Button::South);
+        actions.maintain(&mut input);
+        assert!(attack.get().is_down());
+        assert!(!input.inject_binding(InputBinding::GamepadButton(Button::South), false));
+    }
+}
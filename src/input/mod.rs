@@ -0,0 +1,8 @@
+pub mod actions;
+pub mod buffer;
+pub mod capture;
+pub mod inject;
+pub mod picking;
+pub mod players;
+pub mod prompts;
+pub mod touch;
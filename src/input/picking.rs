@@ -0,0 +1,282 @@
+use crate::{grid_world::GridWorld, third_party::windowing::event::MouseButton};
+use spitfire_draw::{sprite::Sprite, utils::Vertex};
+use spitfire_glow::graphics::Graphics;
+use spitfire_input::{
+    InputActionRef, InputAxisRef, InputContext, InputMapping, VirtualAction, VirtualAxis,
+};
+use typid::ID;
+use vek::{Rect, Vec2};
+
+/// Converts screen position in pixels to world position, through main camera.
+pub fn screen_to_world(graphics: &Graphics<Vertex>, position: Vec2<f32>) -> Vec2<f32> {
+    let position = graphics.main_camera.screen_matrix().mul_point(position);
+    graphics
+        .main_camera
+        .world_matrix()
+        .inverted()
+        .mul_point(position)
+}
+
+/// Converts world position to screen position in pixels, through main camera.
+pub fn world_to_screen(graphics: &Graphics<Vertex>, position: Vec2<f32>) -> Vec2<f32> {
+    let position = graphics.main_camera.world_matrix().mul_point(position);
+    graphics
+        .main_camera
+        .screen_matrix()
+        .inverted()
+        .mul_point(position)
+}
+
+/// Converts world position to normalized screen anchor, useful for GUI layouts.
+pub fn world_to_screen_anchor(graphics: &Graphics<Vertex>, position: Vec2<f32>) -> Vec2<f32> {
+    let position = graphics.main_camera.world_matrix().mul_point(position);
+    Vec2 {
+        x: (position.x + 1.0) * 0.5,
+        y: (-position.y + 1.0) * 0.5,
+    }
+}
+
+/// World space area of pickable object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PickShape {
+    Rectangle(Rect<f32, f32>),
+    Circle { center: Vec2<f32>, radius: f32 },
+}
+
+impl PickShape {
+    /// Axis-aligned bounds of sprite, using its size or given size when sprite has none.
+    /// Sprite rotation is ignored.
+    pub fn sprite(sprite: &Sprite, size: Vec2<f32>) -> Self {
+        let size = sprite.size.unwrap_or(size) * Vec2::from(sprite.transform.scale);
+        let position = Vec2::from(sprite.transform.position) - size * sprite.pivot;
+        Self::Rectangle(Rect::new(position.x, position.y, size.x, size.y))
+    }
+
+    pub fn contains(&self, point: Vec2<f32>) -> bool {
+        match self {
+            Self::Rectangle(rectangle) => rectangle.contains_point(point),
+            Self::Circle { center, radius } => center.distance_squared(point) <= radius * radius,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickEvent<T> {
+    HoverEnter(T),
+    HoverExit(T),
+    Pressed(T),
+    /// Pointer was pressed and released over the same object.
+    Clicked(T),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PickTarget<T> {
+    id: T,
+    shape: PickShape,
+    z: f32,
+}
+
+/// Picks registered objects under mouse pointer in world space, reporting hover
+/// and click events. Objects with higher `z` are on top, and for equal `z` the
+/// later registered one wins.
+pub struct PointerPicker<T: Copy + PartialEq> {
+    pub pointer_x: InputAxisRef,
+    pub pointer_y: InputAxisRef,
+    pub button: InputActionRef,
+    mapping: Option<ID<InputMapping>>,
+    targets: Vec<PickTarget<T>>,
+    screen_position: Vec2<f32>,
+    world_position: Vec2<f32>,
+    hovered: Option<T>,
+    pressed: Option<T>,
+    events: Vec<PickEvent<T>>,
+}
+
+impl<T: Copy + PartialEq> Default for PointerPicker<T> {
+    fn default() -> Self {
+        Self {
+            pointer_x: Default::default(),
+            pointer_y: Default::default(),
+            button: Default::default(),
+            mapping: None,
+            targets: Default::default(),
+            screen_position: Default::default(),
+            world_position: Default::default(),
+            hovered: None,
+            pressed: None,
+            events: Default::default(),
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> PointerPicker<T> {
+    pub fn activate(&mut self, input: &mut InputContext) {
+        if self.mapping.is_none() {
+            self.mapping = Some(
+                input.push_mapping(
+                    InputMapping::default()
+                        .axis(VirtualAxis::MousePositionX, self.pointer_x.clone())
+                        .axis(VirtualAxis::MousePositionY, self.pointer_y.clone())
+                        .action(
+                            VirtualAction::MouseButton(MouseButton::Left),
+                            self.button.clone(),
+                        ),
+                ),
+            );
+        }
+    }
+
+    pub fn deactivate(&mut self, input: &mut InputContext) {
+        if let Some(id) = self.mapping.take() {
+            input.remove_mapping(id);
+        }
+    }
+
+    /// Registers object or updates its shape if already registered.
+    pub fn register(&mut self, id: T, shape: PickShape, z: f32) {
+        if let Some(target) = self.targets.iter_mut().find(|target| target.id == id) {
+            target.shape = shape;
+            target.z = z;
+        } else {
+            self.targets.push(PickTarget { id, shape, z });
+        }
+    }
+
+    /// Registers sprite bounds, with `z` taken from sprite position.
+    pub fn register_sprite(&mut self, id: T, sprite: &Sprite, size: Vec2<f32>) {
+        self.register(
+            id,
+            PickShape::sprite(sprite, size),
+            sprite.transform.position.z,
+        );
+    }
+
+    pub fn unregister(&mut self, id: T) {
+        self.targets.retain(|target| target.id != id);
+        if self.hovered == Some(id) {
+            self.hovered = None;
+        }
+        if self.pressed == Some(id) {
+            self.pressed = None;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.targets.clear();
+        self.hovered = None;
+        self.pressed = None;
+    }
+
+    pub fn screen_position(&self) -> Vec2<f32> {
+        self.screen_position
+    }
+
+    pub fn world_position(&self) -> Vec2<f32> {
+        self.world_position
+    }
+
+    /// Grid world tile under pointer.
+    pub fn tile(&self, grid: &GridWorld) -> Option<Vec2<usize>> {
+        grid.world_to_local(self.world_position)
+    }
+
+    pub fn hovered(&self) -> Option<T> {
+        self.hovered
+    }
+
+    /// Events gathered during last update.
+    pub fn events(&self) -> &[PickEvent<T>] {
+        &self.events
+    }
+
+    /// Topmost object at world position.
+    pub fn pick(&self, position: Vec2<f32>) -> Option<T> {
+        self.targets
+            .iter()
+            .enumerate()
+            .filter(|(_, target)| target.shape.contains(position))
+            .max_by(|(a_index, a), (b_index, b)| {
+                a.z.partial_cmp(&b.z)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a_index.cmp(b_index))
+            })
+            .map(|(_, target)| target.id)
+    }
+
+    /// Reads pointer from input and updates hover and click state.
+    pub fn update(&mut self, graphics: &Graphics<Vertex>) {
+        self.screen_position = Vec2::new(self.pointer_x.get().0, self.pointer_y.get().0);
+        self.world_position = screen_to_world(graphics, self.screen_position);
+        self.update_at(self.world_position);
+    }
+
+    /// Updates hover and click state for pointer at world position.
+    pub fn update_at(&mut self, position: Vec2<f32>) {
+        self.events.clear();
+        self.world_position = position;
+        let hovered = self.pick(position);
+        if hovered != self.hovered {
+            if let Some(id) = self.hovered {
+                self.events.push(PickEvent::HoverExit(id));
+            }
+            if let Some(id) = hovered {
+                self.events.push(PickEvent::HoverEnter(id));
+            }
+            self.hovered = hovered;
+        }
+        let button = self.button.get();
+        if button.is_pressed() {
+            self.pressed = hovered;
+            if let Some(id) = hovered {
+                self.events.push(PickEvent::Pressed(id));
+            }
+        } else if !button.is_down() {
+            if let Some(id) = self.pressed.take() {
+                if hovered == Some(id) {
+                    self.events.push(PickEvent::Clicked(id));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pointer_picking() {
+        let mut picker = PointerPicker::default();
+        picker.register(
+            0,
+            PickShape::Rectangle(Rect::new(0.0, 0.0, 10.0, 10.0)),
+            0.0,
+        );
+        picker.register(
+            1,
+            PickShape::Circle {
+                center: Vec2::new(10.0, 10.0),
+                radius: 5.0,
+            },
+            1.0,
+        );
+        assert_eq!(picker.pick(Vec2::new(9.0, 9.0)), Some(1));
+        assert_eq!(picker.pick(Vec2::new(1.0, 1.0)), Some(0));
+        assert_eq!(picker.pick(Vec2::new(-1.0, 1.0)), None);
+
+        picker.update_at(Vec2::new(1.0, 1.0));
+        assert_eq!(picker.events(), &[PickEvent::HoverEnter(0)]);
+        picker.update_at(Vec2::new(9.0, 9.0));
+        assert_eq!(
+            picker.events(),
+            &[PickEvent::HoverExit(0), PickEvent::HoverEnter(1)]
+        );
+
+        picker.button.set(picker.button.get().change(true));
+        picker.update_at(Vec2::new(9.0, 9.0));
+        assert_eq!(picker.events(), &[PickEvent::Pressed(1)]);
+        picker.button.set(picker.button.get().change(false));
+        picker.update_at(Vec2::new(9.0, 9.0));
+        assert_eq!(picker.events(), &[PickEvent::Clicked(1)]);
+    }
+}
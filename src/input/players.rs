@@ -0,0 +1,417 @@
+use crate::{
+    gamepad::{GamepadId, GamepadManager},
+    input::actions::InputActionMap,
+    third_party::windowing::event::VirtualKeyCode,
+};
+use gilrs::Button;
+use spitfire_input::{InputActionRef, InputContext, InputMapping, VirtualAction};
+use std::collections::HashSet;
+use typid::ID;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyboardHalf {
+    Full,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlayerDevice {
+    Keyboard(KeyboardHalf),
+    Gamepad(GamepadId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerSlotEvent {
+    Joined(usize, PlayerDevice),
+    Left(usize),
+    DeviceLost(usize),
+    DeviceRestored(usize),
+    /// Player that lost its device continues with another one.
+    DeviceSwapped(usize, PlayerDevice),
+}
+
+pub struct PlayerSlot {
+    pub device: PlayerDevice,
+    pub actions: InputActionMap,
+    lost: bool,
+}
+
+impl PlayerSlot {
+    pub fn is_lost(&self) -> bool {
+        self.lost
+    }
+}
+
+struct KeyboardJoin {
+    half: KeyboardHalf,
+    join_key: VirtualKeyCode,
+    leave_key: VirtualKeyCode,
+    join: InputActionRef,
+    leave: InputActionRef,
+}
+
+/// Local multiplayer join flow, assigning keyboard halves and gamepads to player slots.
+/// Each joined player gets own actions map, created by factory for its device, with
+/// gamepad input already attached for gamepad devices.
+pub struct PlayerSlots {
+    pub gamepad_join: Button,
+    pub gamepad_leave: Button,
+    pub accept_joins: bool,
+    slots: Vec<Option<PlayerSlot>>,
+    keyboards: Vec<KeyboardJoin>,
+    #[allow(clippy::type_complexity)]
+    factory: Box<dyn Fn(PlayerDevice) -> InputActionMap>,
+    mapping: Option<ID<InputMapping>>,
+    gamepad_pressed: HashSet<(GamepadId, Button)>,
+    events: Vec<PlayerSlotEvent>,
+}
+
+impl PlayerSlots {
+    pub fn new(
+        max_players: usize,
+        factory: impl Fn(PlayerDevice) -> InputActionMap + 'static,
+    ) -> Self {
+        Self {
+            gamepad_join: Button::Start,
+            gamepad_leave: Button::Select,
+            accept_joins: true,
+            slots: (0..max_players).map(|_| None).collect(),
+            keyboards: Default::default(),
+            factory: Box::new(factory),
+            mapping: None,
+            gamepad_pressed: Default::default(),
+            events: Default::default(),
+        }
+    }
+
+    pub fn with_keyboard(
+        mut self,
+        half: KeyboardHalf,
+        join_key: VirtualKeyCode,
+        leave_key: VirtualKeyCode,
+    ) -> Self {
+        self.keyboards.push(KeyboardJoin {
+            half,
+            join_key,
+            leave_key,
+            join: Default::default(),
+            leave: Default::default(),
+        });
+        self
+    }
+
+    pub fn max_players(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn joined_count(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    pub fn player(&self, index: usize) -> Option<&PlayerSlot> {
+        self.slots.get(index)?.as_ref()
+    }
+
+    pub fn player_mut(&mut self, index: usize) -> Option<&mut PlayerSlot> {
+        self.slots.get_mut(index)?.as_mut()
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = (usize, &PlayerSlot)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| Some((index, slot.as_ref()?)))
+    }
+
+    pub fn player_of_device(&self, device: PlayerDevice) -> Option<usize> {
+        self.players()
+            .find(|(_, slot)| slot.device == device)
+            .map(|(index, _)| index)
+    }
+
+    /// Events gathered during last maintenance.
+    pub fn events(&self) -> &[PlayerSlotEvent] {
+        &self.events
+    }
+
+    pub fn activate(&mut self, input: &mut InputContext) {
+        if self.mapping.is_none() {
+            let mut mapping = InputMapping::default();
+            for keyboard in &self.keyboards {
+                mapping = mapping
+                    .action(
+                        VirtualAction::KeyButton(keyboard.join_key),
+                        keyboard.join.clone(),
+                    )
+                    .action(
+                        VirtualAction::KeyButton(keyboard.leave_key),
+                        keyboard.leave.clone(),
+                    );
+            }
+            self.mapping = Some(input.push_mapping(mapping));
+        }
+        for slot in self.slots.iter_mut().flatten() {
+            slot.actions.activate(input);
+        }
+    }
+
+    pub fn deactivate(&mut self, input: &mut InputContext) {
+        if let Some(id) = self.mapping.take() {
+            input.remove_mapping(id);
+        }
+        for slot in self.slots.iter_mut().flatten() {
+            slot.actions.deactivate(input);
+        }
+    }
+
+    /// Assigns device to first free slot, returning player index.
+    pub fn join(
+        &mut self,
+        device: PlayerDevice,
+        input: &mut InputContext,
+        gamepads: &GamepadManager,
+    ) -> Option<usize> {
+        if self.player_of_device(device).is_some() {
+            return None;
+        }
+        let index = self.slots.iter().position(|slot| slot.is_none())?;
+        let actions = self.make_actions(device, gamepads)?;
+        let mut slot = PlayerSlot {
+            device,
+            actions,
+            lost: false,
+        };
+        if self.mapping.is_some() {
+            slot.actions.activate(input);
+        }
+        self.slots[index] = Some(slot);
+        self.events.push(PlayerSlotEvent::Joined(index, device));
+        Some(index)
+    }
+
+    pub fn leave(&mut self, index: usize, input: &mut InputContext) {
+        if let Some(mut slot) = self.slots.get_mut(index).and_then(|slot| slot.take()) {
+            slot.actions.deactivate(input);
+            self.events.push(PlayerSlotEvent::Left(index));
+        }
+    }
+
+    /// Handles join and leave requests, lost and restored gamepads, and maintains
+    /// actions of joined players.
+    pub fn maintain(&mut self, input: &mut InputContext, gamepads: &GamepadManager) {
+        self.events.clear();
+
+        let requests = self
+            .keyboards
+            .iter()
+            .map(|keyboard| {
+                (
+                    PlayerDevice::Keyboard(keyboard.half),
+                    keyboard.join.get().is_pressed(),
+                    keyboard.leave.get().is_pressed(),
+                )
+            })
+            .collect::<Vec<_>>();
+        for (device, join, leave) in requests {
+            if leave {
+                if let Some(index) = self.player_of_device(device) {
+                    self.leave(index, input);
+                }
+            } else if join && self.accept_joins {
+                self.join(device, input, gamepads);
+            }
+        }
+
+        let mut pressed = HashSet::with_capacity(self.gamepad_pressed.len());
+        for id in gamepads.connected_gamepads() {
+            for button in [self.gamepad_join, self.gamepad_leave] {
+                if !gamepads.is_pressed(id, button) {
+                    continue;
+                }
+                pressed.insert((id, button));
+                if self.gamepad_pressed.contains(&(id, button)) {
+                    continue;
+                }
+                let device = PlayerDevice::Gamepad(id);
+                let player = self.player_of_device(device);
+                if button == self.gamepad_leave {
+                    if let Some(index) = player {
+                        self.leave(index, input);
+                    }
+                } else if player.is_none()
+                    && self.accept_joins
+                    && !self.swap_lost(device, input, gamepads)
+                {
+                    self.join(device, input, gamepads);
+                }
+            }
+        }
+        self.gamepad_pressed = pressed;
+
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            let Some(slot) = slot.as_mut() else {
+                continue;
+            };
+            slot.actions.maintain(input);
+            let Some(gamepad) = slot.actions.gamepad_mut() else {
+                continue;
+            };
+            if slot.lost {
+                if let Some(id) = gamepad.lost_id() {
+                    gamepad.acquire_id(id);
+                }
+                if gamepad.is_connected() {
+                    slot.lost = false;
+                    self.events.push(PlayerSlotEvent::DeviceRestored(index));
+                }
+            } else if gamepad.is_lost() {
+                slot.lost = true;
+                self.events.push(PlayerSlotEvent::DeviceLost(index));
+            }
+        }
+    }
+
+    /// Gives device to first player that lost its gamepad.
+    fn swap_lost(
+        &mut self,
+        device: PlayerDevice,
+        input: &mut InputContext,
+        gamepads: &GamepadManager,
+    ) -> bool {
+        let Some(index) = self
+            .slots
+            .iter()
+            .position(|slot| slot.as_ref().map(|slot| slot.lost).unwrap_or_default())
+        else {
+            return false;
+        };
+        let Some(actions) = self.make_actions(device, gamepads) else {
+            return false;
+        };
+        if let Some(slot) = self.slots[index].as_mut() {
+            slot.actions.deactivate(input);
+            slot.device = device;
+            slot.actions = actions;
+            slot.lost = false;
+            if self.mapping.is_some() {
+                slot.actions.activate(input);
+            }
+        }
+        self.events
+            .push(PlayerSlotEvent::DeviceSwapped(index, device));
+        true
+    }
+
+    fn make_actions(
+        &self,
+        device: PlayerDevice,
+        gamepads: &GamepadManager,
+    ) -> Option<InputActionMap> {
+        let actions = (self.factory)(device);
+        match device {
+            PlayerDevice::Keyboard(_) => Some(actions),
+            PlayerDevice::Gamepad(id) => {
+                let mut gamepad = gamepads.request_gamepad()?;
+                if !gamepad.acquire_id(id) {
+                    return None;
+                }
+                Some(actions.with_gamepad(Some(gamepad)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gamepad::VirtualGamepads,
+        input::{actions::InputBinding, inject::InputInjector},
+    };
+
+    #[test]
+    fn test_player_slots() {
+        let gamepads = VirtualGamepads::default();
+        let manager = GamepadManager::new_virtual(gamepads.clone());
+        let mut input = InputContext::default();
+        let mut slots = PlayerSlots::new(2, |_| {
+            InputActionMap::default().with_action(
+                "attack",
+                [
+                    InputBinding::Key(VirtualKeyCode::Space),
+                    InputBinding::GamepadButton(Button::South),
+                ],
+            )
+        })
+        .with_keyboard(KeyboardHalf::Left, VirtualKeyCode::Tab, VirtualKeyCode::Q);
+        slots.activate(&mut input);
+
+        let first = gamepads.connect("First");
+        gamepads.press(first, Button::Start);
+        slots.maintain(&mut input, &manager);
+        assert_eq!(
+            slots.events(),
+            &[PlayerSlotEvent::Joined(0, PlayerDevice::Gamepad(first))]
+        );
+        slots.maintain(&mut input, &manager);
+        assert!(slots.events().is_empty());
+        gamepads.release(first, Button::Start);
+
+        input.inject_key(VirtualKeyCode::Tab, true);
+        slots.maintain(&mut input, &manager);
+        assert_eq!(
+            slots.events(),
+            &[PlayerSlotEvent::Joined(
+                1,
+                PlayerDevice::Keyboard(KeyboardHalf::Left)
+            )]
+        );
+        input.maintain();
+        input.inject_key(VirtualKeyCode::Tab, false);
+        input.maintain();
+        assert_eq!(slots.joined_count(), 2);
+
+        gamepads.disconnect(first);
+        slots.maintain(&mut input, &manager);
+        assert_eq!(slots.events(), &[PlayerSlotEvent::DeviceLost(0)]);
+        assert!(slots.player(0).unwrap().is_lost());
+
+        gamepads.reconnect(first);
+        slots.maintain(&mut input, &manager);
+        assert_eq!(slots.events(), &[PlayerSlotEvent::DeviceRestored(0)]);
+        assert!(!slots.player(0).unwrap().is_lost());
+
+        gamepads.disconnect(first);
+        slots.maintain(&mut input, &manager);
+        assert_eq!(slots.events(), &[PlayerSlotEvent::DeviceLost(0)]);
+        let second = gamepads.connect("Second");
+        gamepads.press(second, Button::Start);
+        slots.maintain(&mut input, &manager);
+        assert_eq!(
+            slots.events(),
+            &[PlayerSlotEvent::DeviceSwapped(
+                0,
+                PlayerDevice::Gamepad(second)
+            )]
+        );
+        let player = slots.player(0).unwrap();
+        assert_eq!(player.device, PlayerDevice::Gamepad(second));
+        assert!(!player.is_lost());
+        assert_eq!(
+            player.actions.gamepad().and_then(|gamepad| gamepad.id()),
+            Some(second)
+        );
+        gamepads.release(second, Button::Start);
+
+        gamepads.press(second, Button::Select);
+        slots.maintain(&mut input, &manager);
+        assert_eq!(slots.events(), &[PlayerSlotEvent::Left(0)]);
+        gamepads.release(second, Button::Select);
+
+        input.inject_key(VirtualKeyCode::Q, true);
+        slots.maintain(&mut input, &manager);
+        assert_eq!(slots.events(), &[PlayerSlotEvent::Left(1)]);
+        assert_eq!(slots.joined_count(), 0);
+    }
+}
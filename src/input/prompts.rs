@@ -0,0 +1,355 @@
+use crate::{
+    gamepad::{GamepadId, GamepadManager, GamepadStyle},
+    input::actions::{InputActionMap, InputBinding, InputDevice},
+    third_party::windowing::event::{MouseButton, WindowEvent},
+};
+use gilrs::{Axis, Button};
+use std::collections::HashSet;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActiveInputDevice {
+    #[default]
+    KeyboardMouse,
+    Touch,
+    Gamepad(GamepadStyle),
+}
+
+impl ActiveInputDevice {
+    pub fn kind(self) -> Option<InputDevice> {
+        match self {
+            Self::KeyboardMouse => Some(InputDevice::KeyboardMouse),
+            Self::Touch => None,
+            Self::Gamepad(_) => Some(InputDevice::Gamepad),
+        }
+    }
+}
+
+/// Tracks which device player used last, fed with window events by game instance.
+/// Gamepads have to be polled by game state that owns gamepad manager.
+#[derive(Debug, Default)]
+pub struct InputDeviceTracker {
+    active: ActiveInputDevice,
+    changed: bool,
+    gamepad_pressed: HashSet<(GamepadId, InputBinding)>,
+}
+
+impl InputDeviceTracker {
+    pub fn active(&self) -> ActiveInputDevice {
+        self.active
+    }
+
+    /// Tells if active device changed since last maintenance.
+    pub fn has_changed(&self) -> bool {
+        self.changed
+    }
+
+    pub fn set_active(&mut self, device: ActiveInputDevice) {
+        if self.active != device {
+            self.active = device;
+            self.changed = true;
+        }
+    }
+
+    pub fn on_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. } => {
+                self.set_active(ActiveInputDevice::KeyboardMouse);
+            }
+            WindowEvent::Touch(_) => {
+                self.set_active(ActiveInputDevice::Touch);
+            }
+            _ => {}
+        }
+    }
+
+    /// Switches to gamepad with button or axis pressed since last poll, so gamepad
+    /// held while using other device does not take over.
+    pub fn poll_gamepads(&mut self, gamepads: &GamepadManager) {
+        let current = gamepads
+            .connected_gamepads()
+            .into_iter()
+            .flat_map(|id| {
+                gamepads
+                    .gamepad_pressed_bindings(id)
+                    .into_iter()
+                    .map(move |binding| (id, binding))
+            })
+            .collect::<HashSet<_>>();
+        if let Some((id, _)) = current.difference(&self.gamepad_pressed).next() {
+            self.set_active(ActiveInputDevice::Gamepad(gamepads.gamepad_style(*id)));
+        }
+        self.gamepad_pressed = current;
+    }
+
+    pub fn maintain(&mut self) {
+        self.changed = false;
+    }
+}
+
+/// Resolves action bindings to prompt glyph texture names, for currently active device.
+/// Texture name is glyph set prefix followed by glyph name, e.g. `prompts/xbox/a`.
+#[derive(Debug, Clone)]
+pub struct InputPrompts {
+    pub keyboard: String,
+    pub xbox: String,
+    pub playstation: String,
+    pub nintendo: String,
+}
+
+impl Default for InputPrompts {
+    fn default() -> Self {
+        Self::new("prompts/")
+    }
+}
+
+impl InputPrompts {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            keyboard: format!("{}keyboard/", prefix),
+            xbox: format!("{}xbox/", prefix),
+            playstation: format!("{}playstation/", prefix),
+            nintendo: format!("{}nintendo/", prefix),
+        }
+    }
+
+    /// Texture of first action binding usable with device, or none for touch.
+    pub fn glyph(
+        &self,
+        actions: &InputActionMap,
+        action: &str,
+        device: ActiveInputDevice,
+    ) -> Option<String> {
+        let kind = device.kind()?;
+        actions
+            .bindings(action)
+            .iter()
+            .find(|binding| binding.device() == kind)
+            .map(|binding| self.binding_glyph(*binding, device))
+    }
+
+    pub fn binding_glyph(&self, binding: InputBinding, device: ActiveInputDevice) -> String {
+        let style = match device {
+            ActiveInputDevice::Gamepad(style) => style,
+            _ => Default::default(),
+        };
+        let prefix = match binding.device() {
+            InputDevice::KeyboardMouse => &self.keyboard,
+            InputDevice::Gamepad => match style {
+                GamepadStyle::Xbox => &self.xbox,
+                GamepadStyle::PlayStation => &self.playstation,
+                GamepadStyle::Nintendo => &self.nintendo,
+            },
+        };
+        format!("{}{}", prefix, glyph_name(binding, style))
+    }
+}
+
+/// Glyph name of first action binding usable with device, for text prompts when
+/// there are no glyph textures. None for touch.
+pub fn glyph_text(
+    actions: &InputActionMap,
+    action: &str,
+    device: ActiveInputDevice,
+) -> Option<String> {
+    let style = match device {
+        ActiveInputDevice::Gamepad(style) => style,
+        _ => Default::default(),
+    };
+    let kind = device.kind()?;
+    actions
+        .bindings(action)
+        .iter()
+        .find(|binding| binding.device() == kind)
+        .map(|binding| glyph_name(*binding, style))
+}
+
+/// Glyph name of binding, with gamepad buttons named after labels of given style.
+pub fn glyph_name(binding: InputBinding, style: GamepadStyle) -> String {
+    match binding {
+        InputBinding::Key(key) => format!("{:?}", key).to_lowercase(),
+        InputBinding::Mouse(MouseButton::Other(index)) => format!("mouse_{}", index),
+        InputBinding::Mouse(button) => format!("mouse_{:?}", button).to_lowercase(),
+        InputBinding::GamepadButton(button) => button_glyph_name(button, style).to_owned(),
+        InputBinding::GamepadAxis { axis, positive } => {
+            let (name, negative_suffix, positive_suffix) = match axis {
+                Axis::LeftStickX => ("left_stick", "_left", "_right"),
+                Axis::LeftStickY => ("left_stick", "_down", "_up"),
+                Axis::RightStickX => ("right_stick", "_left", "_right"),
+                Axis::RightStickY => ("right_stick", "_down", "_up"),
+                Axis::DPadX => ("dpad", "_left", "_right"),
+                Axis::DPadY => ("dpad", "_down", "_up"),
+                Axis::LeftZ => (button_glyph_name(Button::LeftTrigger2, style), "", ""),
+                Axis::RightZ => (button_glyph_name(Button::RightTrigger2, style), "", ""),
+                _ => ("unknown", "", ""),
+            };
+            let suffix = if positive {
+                positive_suffix
+            } else {
+                negative_suffix
+            };
+            format!("{}{}", name, suffix)
+        }
+    }
+}
+
+fn button_glyph_name(button: Button, style: GamepadStyle) -> &'static str {
+    match (style, button) {
+        (GamepadStyle::Xbox, Button::South) => "a",
+        (GamepadStyle::Xbox, Button::East) => "b",
+        (GamepadStyle::Xbox, Button::North) => "y",
+        (GamepadStyle::Xbox, Button::West) => "x",
+        (GamepadStyle::Xbox, Button::LeftTrigger) => "lb",
+        (GamepadStyle::Xbox, Button::LeftTrigger2) => "lt",
+        (GamepadStyle::Xbox, Button::RightTrigger) => "rb",
+        (GamepadStyle::Xbox, Button::RightTrigger2) => "rt",
+        (GamepadStyle::Xbox, Button::Select) => "view",
+        (GamepadStyle::Xbox, Button::Start) => "menu",
+        (GamepadStyle::PlayStation, Button::South) => "cross",
+        (GamepadStyle::PlayStation, Button::East) => "circle",
+        (GamepadStyle::PlayStation, Button::North) => "triangle",
+        (GamepadStyle::PlayStation, Button::West) => "square",
+        (GamepadStyle::PlayStation, Button::LeftTrigger) => "l1",
+        (GamepadStyle::PlayStation, Button::LeftTrigger2) => "l2",
+        (GamepadStyle::PlayStation, Button::RightTrigger) => "r1",
+        (GamepadStyle::PlayStation, Button::RightTrigger2) => "r2",
+        (GamepadStyle::PlayStation, Button::Select) => "share",
+        (GamepadStyle::PlayStation, Button::Start) => "options",
+        // Nintendo layout has A and B, X and Y swapped compared to Xbox.
+        (GamepadStyle::Nintendo, Button::South) => "b",
+        (GamepadStyle::Nintendo, Button::East) => "a",
+        (GamepadStyle::Nintendo, Button::North) => "x",
+        (GamepadStyle::Nintendo, Button::West) => "y",
+        (GamepadStyle::Nintendo, Button::LeftTrigger) => "l",
+        (GamepadStyle::Nintendo, Button::LeftTrigger2) => "zl",
+        (GamepadStyle::Nintendo, Button::RightTrigger) => "r",
+        (GamepadStyle::Nintendo, Button::RightTrigger2) => "zr",
+        (GamepadStyle::Nintendo, Button::Select) => "minus",
+        (GamepadStyle::Nintendo, Button::Start) => "plus",
+        (_, Button::Mode) => "home",
+        (_, Button::LeftThumb) => "left_stick_press",
+        (_, Button::RightThumb) => "right_stick_press",
+        (_, Button::DPadUp) => "dpad_up",
+        (_, Button::DPadDown) => "dpad_down",
+        (_, Button::DPadLeft) => "dpad_left",
+        (_, Button::DPadRight) => "dpad_right",
+        (_, Button::C) => "c",
+        (_, Button::Z) => "z",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gamepad::VirtualGamepads, input::inject::key_event,
+        third_party::windowing::event::VirtualKeyCode,
+    };
+
+    #[test]
+    fn test_input_prompts() {
+        assert_eq!(
+            GamepadStyle::detect("Sony Interactive Entertainment Wireless Controller", None),
+            GamepadStyle::PlayStation
+        );
+        assert_eq!(
+            GamepadStyle::detect("Wireless Controller", Some(0x054c)),
+            GamepadStyle::PlayStation
+        );
+        assert_eq!(
+            GamepadStyle::detect("Nintendo Switch Pro Controller", None),
+            GamepadStyle::Nintendo
+        );
+        assert_eq!(
+            GamepadStyle::detect("Generic USB Joystick", None),
+            GamepadStyle::Xbox
+        );
+
+        let actions = InputActionMap::default().with_action(
+            "attack",
+            [
+                InputBinding::Key(VirtualKeyCode::Space),
+                InputBinding::GamepadButton(Button::South),
+            ],
+        );
+        let prompts = InputPrompts::default();
+        assert_eq!(
+            prompts.glyph(&actions, "attack", ActiveInputDevice::KeyboardMouse),
+            Some("prompts/keyboard/space".to_owned())
+        );
+        assert_eq!(
+            prompts.glyph(
+                &actions,
+                "attack",
+                ActiveInputDevice::Gamepad(GamepadStyle::PlayStation)
+            ),
+            Some("prompts/playstation/cross".to_owned())
+        );
+        assert_eq!(
+            prompts.glyph(&actions, "attack", ActiveInputDevice::Touch),
+            None
+        );
+        assert_eq!(
+            glyph_text(
+                &actions,
+                "attack",
+                ActiveInputDevice::Gamepad(GamepadStyle::Nintendo)
+            ),
+            Some("b".to_owned())
+        );
+        assert_eq!(
+            glyph_text(&actions, "attack", ActiveInputDevice::KeyboardMouse),
+            Some("space".to_owned())
+        );
+        assert_eq!(
+            glyph_text(&actions, "attack", ActiveInputDevice::Touch),
+            None
+        );
+        assert_eq!(
+            glyph_name(
+                InputBinding::axis_negative(Axis::LeftStickY),
+                Default::default()
+            ),
+            "left_stick_down"
+        );
+    }
+
+    #[test]
+    fn test_input_device_tracker() {
+        let gamepads = VirtualGamepads::default();
+        let manager = GamepadManager::new_virtual(gamepads.clone());
+        let id = gamepads.connect("Wireless Controller");
+        let mut tracker = InputDeviceTracker::default();
+
+        gamepads.press(id, Button::South);
+        tracker.poll_gamepads(&manager);
+        assert_eq!(
+            tracker.active(),
+            ActiveInputDevice::Gamepad(GamepadStyle::Xbox)
+        );
+
+        // Held button does not take over from keyboard.
+        tracker.on_event(&key_event(VirtualKeyCode::Space, true));
+        tracker.poll_gamepads(&manager);
+        assert_eq!(tracker.active(), ActiveInputDevice::KeyboardMouse);
+
+        gamepads.set_axis(id, Axis::LeftStickX, 0.9);
+        tracker.poll_gamepads(&manager);
+        assert_eq!(
+            tracker.active(),
+            ActiveInputDevice::Gamepad(GamepadStyle::Xbox)
+        );
+        tracker.on_event(&key_event(VirtualKeyCode::Space, true));
+        gamepads.release(id, Button::South);
+        tracker.poll_gamepads(&manager);
+        assert_eq!(tracker.active(), ActiveInputDevice::KeyboardMouse);
+        gamepads.press(id, Button::South);
+        tracker.poll_gamepads(&manager);
+        assert_eq!(
+            tracker.active(),
+            ActiveInputDevice::Gamepad(GamepadStyle::Xbox)
+        );
+    }
+}
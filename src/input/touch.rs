@@ -0,0 +1,461 @@
+use crate::{
+    gamepad::{write_input, write_signed_input, GamepadAxisResponse},
+    input::inject::InputInjector,
+    third_party::windowing::event::{MouseButton, Touch, TouchPhase, WindowEvent},
+};
+use raui_immediate_widgets::core::{
+    image_box, Color, ContentBoxItemLayout, ImageBoxColor, ImageBoxMaterial, ImageBoxProps, Rect,
+};
+use spitfire_input::{InputActionOrAxisRef, InputContext};
+use vek::Vec2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchPoint {
+    pub id: u64,
+    /// Screen position in pixels, where touch started.
+    pub start: Vec2<f32>,
+    /// Current screen position in pixels.
+    pub position: Vec2<f32>,
+    pub started: bool,
+    pub ended: bool,
+}
+
+/// Multi-touch tracking, fed with window events by game instance.
+/// First touch can emulate left mouse button, so mouse bindings and GUI work on phones.
+#[derive(Debug)]
+pub struct TouchInput {
+    pub emulate_mouse: bool,
+    points: Vec<TouchPoint>,
+    mouse_touch: Option<u64>,
+    used: bool,
+}
+
+impl Default for TouchInput {
+    fn default() -> Self {
+        Self {
+            emulate_mouse: true,
+            points: Default::default(),
+            mouse_touch: None,
+            used: false,
+        }
+    }
+}
+
+impl TouchInput {
+    /// Tells if any touch happened so far, useful to decide on showing touch controls.
+    pub fn is_used(&self) -> bool {
+        self.used
+    }
+
+    pub fn touches(&self) -> &[TouchPoint] {
+        &self.points
+    }
+
+    pub fn touch(&self, id: u64) -> Option<&TouchPoint> {
+        self.points.iter().find(|point| point.id == id)
+    }
+
+    pub fn on_event(&mut self, event: &WindowEvent, input: &mut InputContext) {
+        let WindowEvent::Touch(Touch {
+            phase,
+            location,
+            id,
+            ..
+        }) = event
+        else {
+            return;
+        };
+        let position = Vec2::new(location.x as f32, location.y as f32);
+        let emulated = self.mouse_touch == Some(*id);
+        match phase {
+            TouchPhase::Started => {
+                self.used = true;
+                self.points.retain(|point| point.id != *id);
+                self.points.push(TouchPoint {
+                    id: *id,
+                    start: position,
+                    position,
+                    started: true,
+                    ended: false,
+                });
+                if self.emulate_mouse && self.mouse_touch.is_none() {
+                    self.mouse_touch = Some(*id);
+                    input.inject_mouse_position(location.x, location.y);
+                    input.inject_mouse_button(MouseButton::Left, true);
+                }
+            }
+            TouchPhase::Moved => {
+                if let Some(point) = self.points.iter_mut().find(|point| point.id == *id) {
+                    point.position = position;
+                }
+                if emulated {
+                    input.inject_mouse_position(location.x, location.y);
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                if let Some(point) = self.points.iter_mut().find(|point| point.id == *id) {
+                    point.position = position;
+                    point.ended = true;
+                }
+                if emulated {
+                    self.mouse_touch = None;
+                    input.inject_mouse_position(location.x, location.y);
+                    input.inject_mouse_button(MouseButton::Left, false);
+                }
+            }
+        }
+    }
+
+    /// Forgets ended touches and clears started flags.
+    pub fn maintain(&mut self) {
+        self.points.retain(|point| !point.ended);
+        for point in &mut self.points {
+            point.started = false;
+        }
+    }
+}
+
+/// Style of on-screen controls, with colors or images.
+#[derive(Debug, Clone)]
+pub struct TouchControlsStyle {
+    pub base: ImageBoxMaterial,
+    pub knob: ImageBoxMaterial,
+    pub pressed: ImageBoxMaterial,
+}
+
+impl Default for TouchControlsStyle {
+    fn default() -> Self {
+        let color = |a| {
+            ImageBoxMaterial::Color(ImageBoxColor {
+                color: Color {
+                    r: 1.0,
+                    g: 1.0,
+                    b: 1.0,
+                    a,
+                },
+                ..Default::default()
+            })
+        };
+        Self {
+            base: color(0.2),
+            knob: color(0.5),
+            pressed: color(0.7),
+        }
+    }
+}
+
+/// On-screen stick, producing same values as gamepad stick.
+/// Position is in normalized screen space, radius is fraction of screen height.
+#[derive(Debug, Clone)]
+pub struct VirtualJoystick {
+    pub position: Vec2<f32>,
+    pub radius: f32,
+    /// Multiplier of radius, within which touch grabs the stick.
+    pub activation_scale: f32,
+    pub response: GamepadAxisResponse,
+    pub left: InputActionOrAxisRef,
+    pub right: InputActionOrAxisRef,
+    pub down: InputActionOrAxisRef,
+    pub up: InputActionOrAxisRef,
+    touch: Option<u64>,
+    value: Vec2<f32>,
+}
+
+impl VirtualJoystick {
+    pub fn new(
+        position: impl Into<Vec2<f32>>,
+        radius: f32,
+        left: impl Into<InputActionOrAxisRef>,
+        right: impl Into<InputActionOrAxisRef>,
+        down: impl Into<InputActionOrAxisRef>,
+        up: impl Into<InputActionOrAxisRef>,
+    ) -> Self {
+        Self {
+            position: position.into(),
+            radius,
+            activation_scale: 1.5,
+            response: GamepadAxisResponse::new(0.15),
+            left: left.into(),
+            right: right.into(),
+            down: down.into(),
+            up: up.into(),
+            touch: None,
+            value: Default::default(),
+        }
+    }
+
+    pub fn response(mut self, value: GamepadAxisResponse) -> Self {
+        self.response = value;
+        self
+    }
+
+    pub fn activation_scale(mut self, value: f32) -> Self {
+        self.activation_scale = value;
+        self
+    }
+
+    /// Current stick value, with Y axis pointing up like gamepad sticks.
+    pub fn value(&self) -> Vec2<f32> {
+        self.value
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.touch.is_some()
+    }
+
+    fn write(&mut self, value: Vec2<f32>) {
+        self.value = value;
+        write_signed_input(&mut self.left, &mut self.right, value.x);
+        write_signed_input(&mut self.down, &mut self.up, value.y);
+    }
+}
+
+/// On-screen button, pressed while touched.
+/// Position is in normalized screen space, radius is fraction of screen height.
+#[derive(Debug, Clone)]
+pub struct VirtualButton {
+    pub position: Vec2<f32>,
+    pub radius: f32,
+    pub input: InputActionOrAxisRef,
+    touch: Option<u64>,
+}
+
+impl VirtualButton {
+    pub fn new(
+        position: impl Into<Vec2<f32>>,
+        radius: f32,
+        input: impl Into<InputActionOrAxisRef>,
+    ) -> Self {
+        Self {
+            position: position.into(),
+            radius,
+            input: input.into(),
+            touch: None,
+        }
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.touch.is_some()
+    }
+}
+
+/// Set of on-screen joysticks and buttons driven by touches.
+/// Inputs are written only when controls change, so keyboard and gamepad still work.
+#[derive(Debug, Default, Clone)]
+pub struct TouchControls {
+    pub joysticks: Vec<VirtualJoystick>,
+    pub buttons: Vec<VirtualButton>,
+    pub style: TouchControlsStyle,
+}
+
+impl TouchControls {
+    pub fn with_joystick(mut self, joystick: VirtualJoystick) -> Self {
+        self.joysticks.push(joystick);
+        self
+    }
+
+    pub fn with_button(mut self, button: VirtualButton) -> Self {
+        self.buttons.push(button);
+        self
+    }
+
+    pub fn with_style(mut self, style: TouchControlsStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Releases all held controls.
+    pub fn release(&mut self) {
+        for joystick in &mut self.joysticks {
+            if joystick.touch.take().is_some() {
+                joystick.write(Vec2::zero());
+            }
+        }
+        for button in &mut self.buttons {
+            if button.touch.take().is_some() {
+                write_input(&mut button.input, 0.0);
+            }
+        }
+    }
+
+    pub fn update(&mut self, touch: &TouchInput, screen_size: Vec2<f32>) {
+        if screen_size.y <= 0.0 {
+            return;
+        }
+        let local = |point: &TouchPoint, position: Vec2<f32>| {
+            (point.position - position * screen_size) / screen_size.y
+        };
+
+        // Touches are claimed before controls read them, so claiming step already
+        // produces values. Taps that started and ended within one frame still press
+        // buttons, until touch input forgets them.
+        for point in touch.touches() {
+            if !point.started || self.is_claimed(point.id) {
+                continue;
+            }
+            if let Some(joystick) = self.joysticks.iter_mut().find(|joystick| {
+                !point.ended
+                    && local(point, joystick.position).magnitude()
+                        <= joystick.radius * joystick.activation_scale
+            }) {
+                joystick.touch = Some(point.id);
+            } else if let Some(button) = self
+                .buttons
+                .iter_mut()
+                .find(|button| local(point, button.position).magnitude() <= button.radius)
+            {
+                button.touch = Some(point.id);
+                write_input(&mut button.input, 1.0);
+            }
+        }
+
+        for joystick in &mut self.joysticks {
+            let Some(id) = joystick.touch else {
+                continue;
+            };
+            match touch.touch(id).filter(|point| !point.ended) {
+                Some(point) => {
+                    let offset = local(point, joystick.position) / joystick.radius;
+                    let value = Vec2::new(offset.x, -offset.y);
+                    let value = if value.magnitude() > 1.0 {
+                        value.normalized()
+                    } else {
+                        value
+                    };
+                    joystick.write(joystick.response.apply_stick(value));
+                }
+                None => {
+                    joystick.touch = None;
+                    joystick.write(Vec2::zero());
+                }
+            }
+        }
+        for button in &mut self.buttons {
+            let Some(id) = button.touch else {
+                continue;
+            };
+            if touch
+                .touch(id)
+                .map(|point| point.ended && !point.started)
+                .unwrap_or(true)
+            {
+                button.touch = None;
+                write_input(&mut button.input, 0.0);
+            }
+        }
+    }
+
+    /// Draws controls as root GUI widgets, using screen anchors so it works with any
+    /// coords mapping.
+    pub fn draw_gui(&self, screen_size: Vec2<f32>) {
+        if screen_size.x <= 0.0 || screen_size.y <= 0.0 {
+            return;
+        }
+        let aspect = screen_size.y / screen_size.x;
+        let layout = |position: Vec2<f32>, radius: f32| ContentBoxItemLayout {
+            anchors: Rect {
+                left: position.x - radius * aspect,
+                right: position.x + radius * aspect,
+                top: position.y - radius,
+                bottom: position.y + radius,
+            },
+            ..Default::default()
+        };
+        let image = |material: &ImageBoxMaterial| ImageBoxProps {
+            material: material.clone(),
+            ..Default::default()
+        };
+
+        for joystick in &self.joysticks {
+            image_box((
+                layout(joystick.position, joystick.radius),
+                image(&self.style.base),
+            ));
+            let knob = joystick.position
+                + Vec2::new(joystick.value.x, -joystick.value.y) * joystick.radius;
+            image_box((layout(knob, joystick.radius * 0.4), image(&self.style.knob)));
+        }
+        for button in &self.buttons {
+            let material = if button.is_pressed() {
+                &self.style.pressed
+            } else {
+                &self.style.base
+            };
+            image_box((layout(button.position, button.radius), image(material)));
+        }
+    }
+
+    fn is_claimed(&self, id: u64) -> bool {
+        self.joysticks
+            .iter()
+            .any(|joystick| joystick.touch == Some(id))
+            || self.buttons.iter().any(|button| button.touch == Some(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spitfire_input::InputActionRef;
+
+    #[test]
+    fn test_touch_controls() {
+        let [left, right, down, up, attack] = std::array::from_fn(|_| InputActionRef::default());
+        let mut controls = TouchControls::default()
+            .with_joystick(VirtualJoystick::new(
+                [0.25, 0.5],
+                0.1,
+                left.clone(),
+                right.clone(),
+                down.clone(),
+                up.clone(),
+            ))
+            .with_button(VirtualButton::new([0.75, 0.5], 0.1, attack.clone()));
+        let screen_size = Vec2::new(200.0, 100.0);
+        let mut touch = TouchInput::default();
+        touch.points.push(TouchPoint {
+            id: 0,
+            start: Vec2::new(50.0, 50.0),
+            position: Vec2::new(58.0, 50.0),
+            started: true,
+            ended: false,
+        });
+        touch.points.push(TouchPoint {
+            id: 1,
+            start: Vec2::new(150.0, 52.0),
+            position: Vec2::new(150.0, 52.0),
+            started: true,
+            ended: false,
+        });
+        controls.update(&touch, screen_size);
+        assert!(right.get().is_down());
+        assert!(!left.get().is_down());
+        assert!(!up.get().is_down() && !down.get().is_down());
+        assert!(attack.get().is_down());
+
+        touch.maintain();
+        touch.points[0].position = Vec2::new(50.0, 40.0);
+        touch.points[1].ended = true;
+        controls.update(&touch, screen_size);
+        assert!(up.get().is_down());
+        assert!(!right.get().is_down());
+        assert!(!attack.get().is_down());
+
+        touch.maintain();
+        touch.points.push(TouchPoint {
+            id: 2,
+            start: Vec2::new(150.0, 50.0),
+            position: Vec2::new(150.0, 50.0),
+            started: true,
+            ended: true,
+        });
+        controls.update(&touch, screen_size);
+        assert!(attack.get().is_pressed());
+        controls.update(&touch, screen_size);
+        assert!(attack.get().is_down());
+        touch.maintain();
+        controls.update(&touch, screen_size);
+        assert!(!attack.get().is_down());
+        assert!(!controls.buttons[0].is_pressed());
+    }
+}
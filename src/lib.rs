@@ -1,7 +1,9 @@
 pub mod third_party {
     pub use anim8;
     pub use anput;
+    pub use base64;
     pub use emergent;
+    pub use flate2;
     pub use fontdue;
     pub use getrandom;
     pub use gilrs;
@@ -23,6 +25,7 @@ pub mod third_party {
     pub use raui_core;
     pub use raui_immediate;
     pub use raui_immediate_widgets;
+    pub use roxmltree;
     pub use rstar;
     pub use rusty_spine;
     pub use serde;
@@ -45,17 +48,20 @@ pub mod third_party {
 pub mod animation;
 pub mod assets;
 pub mod audio;
+pub mod bitmap_font;
 pub mod character;
 pub mod config;
 pub mod context;
 pub mod game;
 pub mod gamepad;
 pub mod grid_world;
+pub mod input;
+pub mod localization;
 pub mod pcg;
 pub mod scripting;
 pub mod tag;
 
-use config::Config;
+use config::{Config, ConfigLoader};
 use game::GameInstance;
 use spitfire_draw::utils::Vertex;
 use spitfire_glow::app::App;
@@ -94,6 +100,11 @@ impl GameLauncher {
         Ok(self)
     }
 
+    pub fn load_config(mut self, loader: ConfigLoader) -> Result<Self, Box<dyn Error>> {
+        self.config = loader.load()?;
+        Ok(self)
+    }
+
     pub fn load_config_from_str(mut self, config: &str) -> Result<Self, Box<dyn Error>> {
         self.config = Config::load_from_str(config)?;
         Ok(self)
@@ -102,6 +113,7 @@ impl GameLauncher {
     pub fn run(self) {
         #[cfg(debug_assertions)]
         spitfire_glow::console_log!("* Game {:#?}", self.config);
-        App::<Vertex>::new(self.config.to_app_config(self.title)).run(self.instance);
+        App::<Vertex>::new(self.config.to_app_config(self.title))
+            .run(self.instance.with_config(self.config));
     }
 }
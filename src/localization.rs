@@ -0,0 +1,398 @@
+use crate::assets::font::{FallbackText, FontFallbackChain};
+use serde::{Deserialize, Serialize};
+use spitfire_draw::{context::DrawContext, text::Text};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::Display,
+};
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "zero" => Some(Self::Zero),
+            "one" => Some(Self::One),
+            "two" => Some(Self::Two),
+            "few" => Some(Self::Few),
+            "many" => Some(Self::Many),
+            "other" => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    /// Cardinal plural rule of given language for integer count.
+    pub fn select(language: &str, count: i64) -> Self {
+        let language = language.split(['-', '_']).next().unwrap_or_default();
+        let n = count.unsigned_abs();
+        let n10 = n % 10;
+        let n100 = n % 100;
+        match language {
+            "ja" | "zh" | "ko" | "th" | "vi" | "id" | "ms" | "tr" => Self::Other,
+            "fr" | "pt" => {
+                if n <= 1 {
+                    Self::One
+                } else {
+                    Self::Other
+                }
+            }
+            "ru" | "uk" | "be" | "sr" | "hr" | "bs" => {
+                if n10 == 1 && n100 != 11 {
+                    Self::One
+                } else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {
+                    Self::Few
+                } else {
+                    Self::Many
+                }
+            }
+            "pl" => {
+                if n == 1 {
+                    Self::One
+                } else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {
+                    Self::Few
+                } else {
+                    Self::Many
+                }
+            }
+            "cs" | "sk" => match n {
+                1 => Self::One,
+                2..=4 => Self::Few,
+                _ => Self::Other,
+            },
+            "ar" => match n {
+                0 => Self::Zero,
+                1 => Self::One,
+                2 => Self::Two,
+                _ if (3..=10).contains(&n100) => Self::Few,
+                _ if (11..=99).contains(&n100) => Self::Many,
+                _ => Self::Other,
+            },
+            _ => {
+                if n == 1 {
+                    Self::One
+                } else {
+                    Self::Other
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocalizedText {
+    Simple(String),
+    Plural(BTreeMap<PluralCategory, String>),
+}
+
+impl LocalizedText {
+    pub fn text(&self, category: PluralCategory) -> &str {
+        match self {
+            Self::Simple(text) => text,
+            Self::Plural(variants) => variants
+                .get(&category)
+                .or_else(|| variants.get(&PluralCategory::Other))
+                .or_else(|| variants.values().next())
+                .map(|text| text.as_str())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Key-value string table, where nested TOML tables build dotted keys and tables with
+/// plural category keys (`one`, `few`, `other`, ...) become plural variants.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StringTable {
+    pub entries: HashMap<String, LocalizedText>,
+}
+
+impl StringTable {
+    pub fn load_from_str(content: &str) -> Result<Self, Box<dyn Error>> {
+        let mut result = Self::default();
+        result.collect(String::new(), content.parse::<toml::Table>()?)?;
+        Ok(result)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&LocalizedText> {
+        self.entries.get(key)
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.entries.extend(other.entries);
+    }
+
+    fn collect(&mut self, prefix: String, table: toml::Table) -> Result<(), Box<dyn Error>> {
+        for (key, value) in table {
+            let key = if prefix.is_empty() {
+                key
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            match value {
+                toml::Value::String(text) => {
+                    self.entries.insert(key, LocalizedText::Simple(text));
+                }
+                toml::Value::Table(table) if Self::is_plural(&table) => {
+                    let variants = table
+                        .into_iter()
+                        .filter_map(|(name, value)| {
+                            Some((
+                                PluralCategory::from_name(&name)?,
+                                value.as_str()?.to_owned(),
+                            ))
+                        })
+                        .collect();
+                    self.entries.insert(key, LocalizedText::Plural(variants));
+                }
+                toml::Value::Table(table) => {
+                    self.collect(key, table)?;
+                }
+                _ => return Err(format!("Localized text `{}` is not a string", key).into()),
+            }
+        }
+        Ok(())
+    }
+
+    fn is_plural(table: &toml::Table) -> bool {
+        table.contains_key("other")
+            && table
+                .iter()
+                .all(|(name, value)| PluralCategory::from_name(name).is_some() && value.is_str())
+    }
+}
+
+/// Replaces `{name}` (or Fluent-like `{ $name }`) placeholders with argument values.
+/// Double braces produce literal brace characters.
+pub fn format_placeholders(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(['{', '}']) {
+        result.push_str(&rest[..index]);
+        let brace = rest.as_bytes()[index];
+        rest = &rest[index + 1..];
+        if rest.as_bytes().first() == Some(&brace) {
+            result.push(brace as char);
+            rest = &rest[1..];
+        } else if brace == b'{' {
+            if let Some(end) = rest.find('}') {
+                let name = rest[..end].trim().trim_start_matches('$').trim();
+                match args.iter().find(|(key, _)| *key == name) {
+                    Some((_, value)) => result.push_str(&value.to_string()),
+                    None => {
+                        result.push('{');
+                        result.push_str(&rest[..=end]);
+                    }
+                }
+                rest = &rest[end + 1..];
+            } else {
+                result.push('{');
+            }
+        } else {
+            result.push('}');
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+pub struct Localization {
+    pub default_language: String,
+    pub tables: HashMap<String, StringTable>,
+    /// Fallback chains keyed by primary font name.
+    pub font_fallbacks: HashMap<String, FontFallbackChain>,
+    language: String,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self {
+            default_language: DEFAULT_LANGUAGE.to_owned(),
+            tables: Default::default(),
+            font_fallbacks: Default::default(),
+            language: DEFAULT_LANGUAGE.to_owned(),
+        }
+    }
+}
+
+impl Localization {
+    pub fn with_language(mut self, language: impl ToString) -> Self {
+        self.language = language.to_string();
+        self
+    }
+
+    pub fn with_default_language(mut self, language: impl ToString) -> Self {
+        self.default_language = language.to_string();
+        self
+    }
+
+    pub fn with_table(mut self, language: impl ToString, table: StringTable) -> Self {
+        self.add_table(language, table);
+        self
+    }
+
+    pub fn with_font_fallback(mut self, font: impl ToString, chain: FontFallbackChain) -> Self {
+        self.font_fallbacks.insert(font.to_string(), chain);
+        self
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn set_language(&mut self, language: impl ToString) {
+        self.language = language.to_string();
+    }
+
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.tables.keys().map(|language| language.as_str())
+    }
+
+    pub fn add_table(&mut self, language: impl ToString, table: StringTable) {
+        self.tables
+            .entry(language.to_string())
+            .or_default()
+            .merge(table);
+    }
+
+    /// Finds text in current language, then default language.
+    pub fn find(&self, key: &str) -> Option<(&str, &LocalizedText)> {
+        [&self.language, &self.default_language]
+            .into_iter()
+            .find_map(|language| {
+                let text = self.tables.get(language)?.get(key)?;
+                Some((language.as_str(), text))
+            })
+    }
+
+    pub fn has(&self, key: &str) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Returns localized text, or key itself when text is missing in all languages.
+    pub fn get<'a>(&'a self, key: &'a str) -> Cow<'a, str> {
+        match self.find(key) {
+            Some((_, text)) => Cow::Borrowed(text.text(PluralCategory::Other)),
+            None => Cow::Borrowed(key),
+        }
+    }
+
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        match self.find(key) {
+            Some((_, text)) => format_placeholders(text.text(PluralCategory::Other), args),
+            None => key.to_owned(),
+        }
+    }
+
+    /// Formats plural text variant for count, which is also available as `{count}` placeholder.
+    pub fn format_plural(&self, key: &str, count: i64, args: &[(&str, &dyn Display)]) -> String {
+        match self.find(key) {
+            Some((language, text)) => {
+                let text = text.text(PluralCategory::select(language, count));
+                let mut args = args.to_vec();
+                args.push(("count", &count));
+                format_placeholders(text, &args)
+            }
+            None => key.to_owned(),
+        }
+    }
+
+    /// Fallback chain registered for given font, or chain with only that font.
+    pub fn font_chain(&self, font: &str) -> FontFallbackChain {
+        self.font_fallbacks
+            .get(font)
+            .cloned()
+            .unwrap_or_else(|| FontFallbackChain::new(font))
+    }
+
+    /// Wraps text to render glyphs missing in its font with fonts of its fallback chain.
+    pub fn fallback_text(&self, text: Text) -> FallbackText {
+        let chain = self.font_chain(&text.font);
+        FallbackText::new(text, chain)
+    }
+
+    /// Picks font from fallback chain of given font, able to render whole text.
+    pub fn font<'a>(&'a self, font: &'a str, text: &str, draw: &DrawContext) -> &'a str {
+        self.font_fallbacks
+            .get(font)
+            .and_then(|chain| chain.select(text, draw))
+            .unwrap_or(font)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localization() {
+        let en = StringTable::load_from_str(
+            r#"
+            title = "Red Hood"
+
+            [menu]
+            play = "Play"
+            exit = "Exit"
+
+            [enemies]
+            one = "{count} enemy left"
+            other = "{ $count } enemies left"
+            "#,
+        )
+        .unwrap();
+        let pl = StringTable::load_from_str(
+            r#"
+            [menu]
+            play = "Graj"
+
+            [enemies]
+            one = "Został {count} wróg"
+            few = "Zostali {count} wrogowie"
+            many = "Zostało {count} wrogów"
+            other = "Zostało {count} wroga"
+            "#,
+        )
+        .unwrap();
+        let mut localization = Localization::default()
+            .with_table("en", en)
+            .with_table("pl", pl);
+
+        assert_eq!(localization.get("menu.play"), "Play");
+        assert_eq!(
+            localization.format_plural("enemies", 1, &[]),
+            "1 enemy left"
+        );
+        assert_eq!(
+            localization.format_plural("enemies", 3, &[]),
+            "3 enemies left"
+        );
+
+        localization.set_language("pl");
+        assert_eq!(localization.get("menu.play"), "Graj");
+        assert_eq!(localization.get("menu.exit"), "Exit");
+        assert_eq!(localization.get("missing"), "missing");
+        assert_eq!(
+            localization.format_plural("enemies", 22, &[]),
+            "Zostali 22 wrogowie"
+        );
+
+        assert_eq!(
+            format_placeholders("{{{name}}} {missing} }}", &[("name", &"hood")]),
+            "{hood} {missing} }"
+        );
+        assert_eq!(PluralCategory::select("ru", 21), PluralCategory::One);
+        assert_eq!(PluralCategory::select("ru", 11), PluralCategory::Many);
+        assert_eq!(PluralCategory::select("ja", 1), PluralCategory::Other);
+    }
+}
@@ -2,10 +2,19 @@ pub mod states;
 
 use self::states::gameplay::Gameplay;
 use micro_games_kit::{
-    assets::make_memory_database, config::Config, game::GameInstance, GameLauncher,
+    assets::make_memory_database,
+    config::{user_config_directory, ConfigLoader},
+    game::GameInstance,
+    GameLauncher,
 };
 
 pub fn main() {
+    let mut config =
+        ConfigLoader::default().shipped_str(include_str!("../../assets/GameConfig.toml"));
+    if let Some(directory) = user_config_directory("micro-game") {
+        config = config.user_file(directory.join("GameConfig.toml"));
+    }
+
     GameLauncher::new(
         GameInstance::new(Gameplay::default()).setup_assets(|assets| {
             *assets = make_memory_database(include_bytes!("../../assets.pack")).unwrap();
@@ -13,7 +22,10 @@ pub fn main() {
     )
     .title("Micro Game")
     .config(
-        Config::load_from_str(include_str!("../../assets/GameConfig.toml"))
+        config
+            .env("MICRO_GAME")
+            .args(std::env::args().skip(1))
+            .load()
             .expect("Could not load Game Config!"),
     )
     .run();
@@ -1,13 +1,11 @@
 use micro_games_kit::{
+    assets::texture::sprite,
     context::GameContext,
     game::GameObject,
     third_party::{
+        keket::database::AssetDatabase,
         rand::random,
-        spitfire_draw::{
-            sprite::{Sprite, SpriteTexture},
-            utils::{Drawable, TextureRef},
-        },
-        spitfire_glow::renderer::GlowTextureFiltering,
+        spitfire_draw::{sprite::Sprite, utils::Drawable},
         vek::Vec2,
     },
 };
@@ -62,14 +60,9 @@ pub struct Item {
 }
 
 impl Item {
-    pub fn new(kind: ItemKind, position: impl Into<Vec2<f32>>) -> Self {
+    pub fn new(kind: ItemKind, position: impl Into<Vec2<f32>>, assets: &AssetDatabase) -> Self {
         Self {
-            sprite: Sprite::single(SpriteTexture {
-                sampler: "u_image".into(),
-                texture: TextureRef::name(kind.texture()),
-                filtering: GlowTextureFiltering::Linear,
-            })
-            .position(position.into()),
+            sprite: sprite(assets, "u_image", kind.texture()).position(position.into()),
             health: kind.health(),
             attack: kind.attack(),
         }
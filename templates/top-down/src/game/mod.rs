@@ -2,23 +2,42 @@ pub mod drawables;
 pub mod enemy;
 pub mod item;
 pub mod player;
+pub mod settings;
 pub mod states;
 pub mod torch;
 pub mod ui;
 pub mod utils;
 
-use self::states::preloader::Preloader;
+use self::{
+    settings::{Difficulty, BINDINGS_SECTION, DIFFICULTY_SECTION},
+    states::preloader::Preloader,
+};
 use micro_games_kit::{
-    assets::make_memory_database, config::Config, game::GameInstance, GameLauncher,
+    assets::make_memory_database,
+    config::{user_config_directory, ConfigLoader},
+    game::GameInstance,
+    input::actions::InputBindings,
+    GameLauncher,
 };
 
 pub fn main() {
+    let mut config = ConfigLoader::default()
+        .section::<Difficulty>(DIFFICULTY_SECTION)
+        .section::<InputBindings>(BINDINGS_SECTION)
+        .shipped_str(include_str!("../../assets/GameConfig.toml"));
+    if let Some(directory) = user_config_directory("red-hood") {
+        config = config.user_file(directory.join("GameConfig.toml"));
+    }
+
     GameLauncher::new(GameInstance::new(Preloader).setup_assets(|assets| {
         *assets = make_memory_database(include_bytes!("../../assets.pack")).unwrap();
     }))
     .title("RED HOOD")
     .config(
-        Config::load_from_str(include_str!("../../assets/GameConfig.toml"))
+        config
+            .env("RED_HOOD")
+            .args(std::env::args().skip(1))
+            .load()
             .expect("Could not load Game Config!"),
     )
     .run();
@@ -16,7 +16,7 @@ pub struct PlayerIsAttackingCondition;
 impl Condition<CharacterMemory<PlayerState>> for PlayerIsAttackingCondition {
     fn validate(&self, memory: &CharacterMemory<PlayerState>) -> bool {
         let state = memory.state.read().unwrap();
-        state.input.attack.get().is_down()
+        state.input.attack.get().is_down() || state.input.buffer.is_buffered("attack")
     }
 }
 
@@ -10,6 +10,7 @@ use self::{
 };
 use super::{
     item::Item,
+    settings::BINDINGS_SECTION,
     utils::events::{Event, Events, Instigator},
 };
 use micro_games_kit::{
@@ -17,7 +18,11 @@ use micro_games_kit::{
     character::{Character, CharacterController},
     context::GameContext,
     game::GameObject,
-    gamepad::{GamepadInput, GamepadInputAxis, GamepadManager},
+    gamepad::{GamepadInput, GamepadManager},
+    input::{
+        actions::{InputActionMap, InputBinding, InputBindings},
+        buffer::InputBuffer,
+    },
     third_party::{
         emergent::builders::behavior_tree::BehaviorTree,
         gilrs::{Axis, Button},
@@ -26,9 +31,9 @@ use micro_games_kit::{
             utils::{Drawable, ShaderRef, TextureRef},
         },
         spitfire_glow::renderer::GlowUniformValue,
-        spitfire_input::{CardinalInputCombinator, InputActionRef, InputMapping, VirtualAction},
+        spitfire_input::{CardinalInputCombinator, InputActionRef},
         vek::{Vec2, Vec3},
-        windowing::event::VirtualKeyCode,
+        windowing::event::{MouseButton, VirtualKeyCode},
     },
 };
 
@@ -69,13 +74,94 @@ impl PlayerWeapon {
     }
 }
 
-#[derive(Default)]
 pub struct PlayerInputState {
+    pub actions: InputActionMap,
     pub movement: CardinalInputCombinator,
     pub attack: InputActionRef,
     pub weapon_prev: InputActionRef,
     pub weapon_next: InputActionRef,
-    pub gamepad: Option<GamepadInput>,
+    /// Attack presses buffered for a short moment, so attacks can be chained
+    /// without frame-perfect timing.
+    pub buffer: InputBuffer,
+}
+
+impl Default for PlayerInputState {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl PlayerInputState {
+    pub fn new(gamepad: Option<GamepadInput>) -> Self {
+        let actions = Self::default_actions().with_gamepad(gamepad);
+        Self {
+            movement: CardinalInputCombinator::new(
+                actions.action("move_left"),
+                actions.action("move_right"),
+                actions.action("move_up"),
+                actions.action("move_down"),
+            ),
+            attack: actions.action("attack"),
+            weapon_prev: actions.action("weapon_prev"),
+            weapon_next: actions.action("weapon_next"),
+            buffer: InputBuffer::new(0.25).with_action("attack", actions.action("attack")),
+            actions,
+        }
+    }
+
+    pub fn default_actions() -> InputActionMap {
+        InputActionMap::default()
+            .with_action(
+                "move_left",
+                [
+                    InputBinding::Key(VirtualKeyCode::A),
+                    InputBinding::axis_negative(Axis::LeftStickX),
+                ],
+            )
+            .with_action(
+                "move_right",
+                [
+                    InputBinding::Key(VirtualKeyCode::D),
+                    InputBinding::axis_positive(Axis::LeftStickX),
+                ],
+            )
+            .with_action(
+                "move_up",
+                [
+                    InputBinding::Key(VirtualKeyCode::W),
+                    InputBinding::axis_positive(Axis::LeftStickY),
+                ],
+            )
+            .with_action(
+                "move_down",
+                [
+                    InputBinding::Key(VirtualKeyCode::S),
+                    InputBinding::axis_negative(Axis::LeftStickY),
+                ],
+            )
+            .with_action(
+                "attack",
+                [
+                    InputBinding::Key(VirtualKeyCode::Space),
+                    InputBinding::Mouse(MouseButton::Left),
+                    InputBinding::GamepadButton(Button::South),
+                ],
+            )
+            .with_action(
+                "weapon_prev",
+                [
+                    InputBinding::Key(VirtualKeyCode::Q),
+                    InputBinding::GamepadButton(Button::West),
+                ],
+            )
+            .with_action(
+                "weapon_next",
+                [
+                    InputBinding::Key(VirtualKeyCode::E),
+                    InputBinding::GamepadButton(Button::North),
+                ],
+            )
+    }
 }
 
 pub struct PlayerState {
@@ -112,12 +198,19 @@ impl Default for PlayerState {
 impl GameObject for PlayerState {
     fn activate(&mut self, context: &mut GameContext) {
         context.graphics.main_camera.transform.position = self.sprite.transform.position;
+        if let Ok(bindings) = context.config.section::<InputBindings>(BINDINGS_SECTION) {
+            self.input.actions.apply_bindings(&bindings);
+        }
+        self.input.actions.activate(context.input);
+    }
+
+    fn deactivate(&mut self, context: &mut GameContext) {
+        self.input.actions.deactivate(context.input);
     }
 
     fn process(&mut self, context: &mut GameContext, delta_time: f32) {
-        if let Some(gamepad) = self.input.gamepad.as_mut() {
-            gamepad.apply();
-        }
+        self.input.actions.maintain(context.input);
+        self.input.buffer.update(delta_time);
 
         if self.input.weapon_prev.get().is_pressed() {
             self.weapon = self.weapon.prev();
@@ -161,48 +254,13 @@ impl PlayerState {
         position: impl Into<Vec3<f32>>,
         gamepads: &GamepadManager,
     ) -> Character<PlayerState> {
-        let left = InputActionRef::default();
-        let right = InputActionRef::default();
-        let up = InputActionRef::default();
-        let down = InputActionRef::default();
-
         let mut state = PlayerState::default();
-        state.input.movement =
-            CardinalInputCombinator::new(left.clone(), right.clone(), up.clone(), down.clone());
         state.sprite.transform.position = position.into();
-        state.input.gamepad = gamepads.request_gamepad().map(|gamepad| {
-            gamepad
-                .auto_acquire()
-                .axis(
-                    Axis::LeftStickX,
-                    GamepadInputAxis::double(left.clone(), right.clone(), 0.1),
-                )
-                .axis(
-                    Axis::LeftStickY,
-                    GamepadInputAxis::double(down.clone(), up.clone(), 0.1),
-                )
-                .button(Button::South, state.input.attack.clone())
-                .button(Button::West, state.input.weapon_prev.clone())
-                .button(Button::North, state.input.weapon_next.clone())
-        });
-
-        let mapping = InputMapping::default()
-            .action(VirtualAction::KeyButton(VirtualKeyCode::A), left)
-            .action(VirtualAction::KeyButton(VirtualKeyCode::D), right)
-            .action(VirtualAction::KeyButton(VirtualKeyCode::W), up)
-            .action(VirtualAction::KeyButton(VirtualKeyCode::S), down)
-            .action(
-                VirtualAction::KeyButton(VirtualKeyCode::Space),
-                state.input.attack.clone(),
-            )
-            .action(
-                VirtualAction::KeyButton(VirtualKeyCode::Q),
-                state.input.weapon_prev.clone(),
-            )
-            .action(
-                VirtualAction::KeyButton(VirtualKeyCode::E),
-                state.input.weapon_next.clone(),
-            );
+        state.input = PlayerInputState::new(
+            gamepads
+                .request_gamepad()
+                .map(|gamepad| gamepad.auto_acquire()),
+        );
 
         let task = BehaviorTree::selector(true)
             .node(
@@ -223,8 +281,7 @@ impl PlayerState {
             .node(BehaviorTree::state(true, PlayerIdleTask::default()))
             .build();
 
-        let controller = CharacterController::input(mapping);
-        Character::new(state, task, controller)
+        Character::new(state, task, CharacterController::None)
     }
 
     pub fn apply_animation(&mut self, animation: &NamedAnimation) {
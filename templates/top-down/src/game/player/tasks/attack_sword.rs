@@ -30,7 +30,8 @@ impl Task<CharacterMemory<PlayerState>> for PlayerAttackSwordTask {
     }
 
     fn on_enter(&mut self, memory: &mut CharacterMemory<PlayerState>) {
-        let state = memory.state.read().unwrap();
+        let mut state = memory.state.write().unwrap();
+        state.input.buffer.consume("attack");
 
         self.animation.animation.play();
 
@@ -51,7 +52,10 @@ impl Task<CharacterMemory<PlayerState>> for PlayerAttackSwordTask {
         {
             for event in events {
                 if event == "hit" {
-                    Events::write(Event::PlaySound("sword".into()));
+                    Events::write(Event::PlaySoundAt(
+                        "sword".into(),
+                        memory.state.read().unwrap().sprite.transform.position.xy(),
+                    ));
                 }
             }
         }
@@ -5,11 +5,7 @@ use crate::game::{
 use micro_games_kit::{
     animation::frame::{FrameAnimation, NamedAnimation},
     character::CharacterMemory,
-    third_party::{
-        emergent::task::Task,
-        rand::{thread_rng, Rng},
-        vek::Vec3,
-    },
+    third_party::{emergent::task::Task, vek::Vec3},
 };
 
 #[derive(Debug, Clone)]
@@ -51,14 +47,9 @@ impl Task<CharacterMemory<PlayerState>> for PlayerRunTask {
         {
             for event in events {
                 if event == "footstep" {
-                    Events::write(Event::PlaySound(
-                        match thread_rng().gen_range(1..=3) {
-                            1 => "footstep/grass/1",
-                            2 => "footstep/grass/2",
-                            3 => "footstep/grass/3",
-                            _ => unreachable!(),
-                        }
-                        .into(),
+                    Events::write(Event::PlaySoundAt(
+                        "footstep".into(),
+                        state.sprite.transform.position.xy(),
                     ));
                 }
             }
@@ -0,0 +1,20 @@
+use micro_games_kit::third_party::serde::{Deserialize, Serialize};
+
+pub const DIFFICULTY_SECTION: &str = "difficulty";
+pub const BINDINGS_SECTION: &str = "bindings";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "micro_games_kit::third_party::serde", default)]
+pub struct Difficulty {
+    pub enemies: usize,
+    pub items: usize,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self {
+            enemies: 6,
+            items: 20,
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use super::main_menu::MainMenu;
+use crate::game::{
+    player::PlayerInputState,
+    settings::BINDINGS_SECTION,
+    ui::{make_theme, text_button::text_button},
+};
+use micro_games_kit::{
+    audio::{MUSIC_BUS, SFX_BUS},
+    context::GameContext,
+    game::{GameState, GameStateChange},
+    gamepad::GamepadManager,
+    input::{
+        actions::{InputActionMap, InputBindings, InputRebindResult},
+        capture::InputCaptureState,
+    },
+    third_party::{
+        raui_immediate::apply_shared_props,
+        raui_immediate_widgets::{
+            core::{containers::nav_vertical_box, FlexBoxItemLayout},
+            material::{text_paper, TextPaperProps},
+        },
+        spitfire_glow::console_log,
+    },
+};
+
+const VOLUME_BUSES: [&str; 2] = [MUSIC_BUS, SFX_BUS];
+
+const ACTIONS: [&str; 7] = [
+    "move_up",
+    "move_down",
+    "move_left",
+    "move_right",
+    "attack",
+    "weapon_prev",
+    "weapon_next",
+];
+
+pub struct Controls {
+    actions: InputActionMap,
+    gamepads: GamepadManager,
+    rebinding: Option<String>,
+    notice: Option<String>,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Self {
+            actions: PlayerInputState::default_actions(),
+            gamepads: Default::default(),
+            rebinding: None,
+            notice: None,
+        }
+    }
+}
+
+impl Controls {
+    fn action_label(context: &GameContext, action: &str) -> String {
+        context
+            .localization
+            .get(&format!("controls.actions.{}", action))
+            .into_owned()
+    }
+
+    fn save(&self, context: &mut GameContext) {
+        let result = context
+            .config
+            .set_section(BINDINGS_SECTION, &self.actions.to_bindings())
+            .and_then(|_| context.save_user_config());
+        if let Err(error) = result {
+            console_log!("* Could not save controls: {}", error);
+        }
+    }
+}
+
+impl GameState for Controls {
+    fn enter(&mut self, context: GameContext) {
+        context.graphics.color = [0.2, 0.2, 0.2, 1.0];
+        context.gui.coords_map_scaling = Default::default();
+
+        if let Ok(bindings) = context.config.section::<InputBindings>(BINDINGS_SECTION) {
+            self.actions.apply_bindings(&bindings);
+        }
+    }
+
+    fn exit(&mut self, context: GameContext) {
+        context.input_capture.cancel();
+        context.input_capture.take();
+    }
+
+    fn update(&mut self, mut context: GameContext, _: f32) {
+        self.gamepads.maintain();
+        context.input_capture.poll_gamepads(&self.gamepads);
+        context.input_device.poll_gamepads(&self.gamepads);
+
+        let Some(action) = self.rebinding.clone() else {
+            return;
+        };
+        match context.input_capture.take() {
+            Some(InputCaptureState::Captured(binding)) => {
+                self.rebinding = None;
+                self.notice = None;
+                if let InputRebindResult::Conflict(conflicts) =
+                    self.actions.rebind(&action, binding)
+                {
+                    let conflicts = conflicts
+                        .iter()
+                        .map(|name| Self::action_label(&context, name))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    self.notice = Some(context.localization.format(
+                        "controls.conflict",
+                        &[("binding", &binding.label()), ("actions", &conflicts)],
+                    ));
+                    self.actions.force_rebind(&action, binding);
+                }
+                self.save(&mut context);
+            }
+            Some(_) => {
+                self.rebinding = None;
+            }
+            None => {}
+        }
+    }
+
+    fn draw_gui(&mut self, mut context: GameContext) {
+        apply_shared_props(make_theme(), || {
+            nav_vertical_box((), || {
+                let button_props = FlexBoxItemLayout {
+                    basis: Some(50.0),
+                    grow: 0.0,
+                    shrink: 0.0,
+                    margin: 6.0.into(),
+                    ..Default::default()
+                };
+
+                text_paper(TextPaperProps {
+                    text: context.localization.get("controls.title").into_owned(),
+                    variant: "title".to_owned(),
+                    color_override: Some(Default::default()),
+                    ..Default::default()
+                });
+
+                for action in ACTIONS {
+                    let bindings = if self.rebinding.as_deref() == Some(action) {
+                        context.localization.get("controls.press").into_owned()
+                    } else {
+                        self.actions
+                            .bindings(action)
+                            .iter()
+                            .map(|binding| binding.label())
+                            .collect::<Vec<_>>()
+                            .join(" / ")
+                    };
+                    let button = text_button(
+                        button_props.clone(),
+                        format!("{}: {}", Self::action_label(&context, action), bindings),
+                    );
+                    if button.trigger_stop() && !context.input_capture.is_listening() {
+                        context.input_capture.start();
+                        self.rebinding = Some(action.to_owned());
+                    }
+                }
+
+                for bus in VOLUME_BUSES {
+                    let volume = context
+                        .audio
+                        .bus(bus)
+                        .map(|bus| bus.volume())
+                        .unwrap_or_default();
+                    let button = text_button(
+                        button_props.clone(),
+                        format!(
+                            "{}: {}%",
+                            context
+                                .localization
+                                .get(&format!("controls.volume.{}", bus)),
+                            (volume * 100.0).round()
+                        ),
+                    );
+                    if button.trigger_stop() {
+                        // Cycles through 0% to 100% in 20% steps.
+                        let volume = if volume >= 0.99 {
+                            0.0
+                        } else {
+                            ((volume + 0.2) * 5.0).round() / 5.0
+                        };
+                        context.audio.set_bus_volume(bus, volume);
+                        self.save(&mut context);
+                    }
+                }
+
+                if let Some(notice) = self.notice.as_ref() {
+                    text_paper(TextPaperProps {
+                        text: notice.to_owned(),
+                        color_override: Some(Default::default()),
+                        ..Default::default()
+                    });
+                }
+
+                let reset = text_button(
+                    button_props.clone(),
+                    context.localization.get("controls.reset"),
+                );
+                if reset.trigger_stop() {
+                    self.actions.reset_all();
+                    self.notice = None;
+                    self.save(&mut context);
+                }
+
+                let back = text_button(button_props, context.localization.get("controls.back"));
+                if back.trigger_stop() {
+                    *context.state_change = GameStateChange::Swap(Box::new(MainMenu));
+                }
+            });
+        });
+    }
+}
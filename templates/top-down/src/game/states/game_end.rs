@@ -91,7 +91,7 @@ impl GameState for GameEnd {
                                 margin: 20.0.into(),
                                 ..Default::default()
                             },
-                            "Restart",
+                            context.localization.get("menu.restart"),
                         );
 
                         let exit = text_button(
@@ -99,7 +99,7 @@ impl GameState for GameEnd {
                                 margin: 20.0.into(),
                                 ..Default::default()
                             },
-                            "Exit",
+                            context.localization.get("menu.exit"),
                         );
 
                         if exit.trigger_stop() {
@@ -7,6 +7,7 @@ use crate::game::{
     enemy::EnemyState,
     item::{Item, ItemKind},
     player::PlayerState,
+    settings::{Difficulty, DIFFICULTY_SECTION},
     torch::Torch,
     ui::{health_bar::health_bar, world_to_screen_content_layout},
     utils::{
@@ -15,23 +16,31 @@ use crate::game::{
     },
 };
 use micro_games_kit::{
+    assets::texture::sprite,
+    audio::music::{MusicCue, MusicDirector, MusicLayer, MusicPlaylist, MusicSync},
     character::Character,
     context::GameContext,
     game::{GameObject, GameState, GameStateChange},
-    gamepad::GamepadManager,
+    gamepad::{GamepadEvent, GamepadManager},
+    input::{
+        prompts::glyph_text,
+        touch::{TouchControls, VirtualButton, VirtualJoystick},
+    },
     third_party::{
-        kira::sound::static_sound::StaticSoundHandle,
         rand::{thread_rng, Rng},
         raui_core::layout::CoordsMappingScaling,
         raui_immediate_widgets::core::{
-            text_box, Color, ContentBoxItemLayout, Rect, TextBoxFont, TextBoxProps,
+            text_box, Color, ContentBoxItemLayout, Rect, TextBoxFont, TextBoxHorizontalAlign,
+            TextBoxProps,
         },
         spitfire_draw::{
             canvas::Canvas,
-            sprite::{Sprite, SpriteTexture},
-            utils::{Drawable, ShaderRef, TextureRef},
+            sprite::Sprite,
+            text::Text,
+            utils::{Drawable, ShaderRef},
         },
         spitfire_glow::{
+            console_log,
             graphics::CameraScaling,
             renderer::{GlowBlending, GlowTextureFiltering, GlowTextureFormat, GlowUniformValue},
         },
@@ -50,35 +59,49 @@ pub struct Gameplay {
     torch: Torch,
     darkness: Option<Canvas>,
     exit: InputActionRef,
+    continue_without_gamepad: InputActionRef,
     exit_handle: Option<ID<InputMapping>>,
     map_radius: f32,
-    music_forest: Option<StaticSoundHandle>,
-    music_battle: Option<StaticSoundHandle>,
     gamepads: GamepadManager,
+    touch_controls: TouchControls,
 }
 
 impl Default for Gameplay {
     fn default() -> Self {
         let gamepads = GamepadManager::default();
+        let player = PlayerState::new_character([0.0, 0.0, 0.0], &gamepads);
+        let touch_controls = {
+            let state = player.state.read().unwrap();
+            let actions = &state.input.actions;
+            TouchControls::default()
+                .with_joystick(VirtualJoystick::new(
+                    [0.15, 0.75],
+                    0.12,
+                    actions.action("move_left"),
+                    actions.action("move_right"),
+                    actions.action("move_down"),
+                    actions.action("move_up"),
+                ))
+                .with_button(VirtualButton::new(
+                    [0.85, 0.75],
+                    0.08,
+                    actions.action("attack"),
+                ))
+        };
 
         Self {
-            map: Sprite::single(SpriteTexture {
-                sampler: "u_image".into(),
-                texture: TextureRef::name("map/level-0"),
-                filtering: GlowTextureFiltering::Linear,
-            })
-            .pivot(0.5.into()),
-            player: PlayerState::new_character([0.0, 0.0, 0.0], &gamepads),
+            map: Default::default(),
+            player,
             enemies: Default::default(),
             items: Default::default(),
             torch: Torch::new([0.0, 0.0]),
             darkness: None,
             exit: Default::default(),
+            continue_without_gamepad: Default::default(),
             exit_handle: None,
             map_radius: 800.0,
-            music_forest: None,
-            music_battle: None,
             gamepads,
+            touch_controls,
         }
     }
 }
@@ -89,31 +112,51 @@ impl GameState for Gameplay {
         context.graphics.main_camera.screen_alignment = 0.5.into();
         context.graphics.main_camera.scaling = CameraScaling::FitVertical(512.0);
         context.gui.coords_map_scaling = CoordsMappingScaling::FitVertical(1024.0);
+        // Touches drive on-screen controls here, so they should not also attack as mouse.
+        context.touch.emulate_mouse = false;
 
-        self.music_forest = {
-            let mut handle = context.audio.play("forest").unwrap();
-            handle.set_volume(0.0, Default::default());
-            handle.set_loop_region(..);
-            Some(handle)
-        };
-
-        self.music_battle = {
-            let mut handle = context.audio.play("battle").unwrap();
-            handle.set_volume(0.0, Default::default());
-            handle.set_loop_region(..);
-            Some(handle)
-        };
+        self.map = sprite(context.assets, "u_image", "map/level-0").pivot(0.5.into());
 
-        self.exit_handle = Some(context.input.push_mapping(
-            InputMapping::default().consume(InputConsume::Hit).action(
-                VirtualAction::KeyButton(VirtualKeyCode::Escape),
-                self.exit.clone(),
+        context.audio.music = MusicDirector::default()
+            .with_cue(
+                "forest",
+                MusicCue::new(MusicLayer::new("forest").with_volume(2.0))
+                    .with_sync(MusicSync::Beat)
+                    .with_fade(2.0, 2.0),
+            )
+            .with_cue(
+                "battle",
+                MusicCue::new(MusicLayer::new("battle").with_volume(2.0))
+                    .with_sync(MusicSync::Beat)
+                    .with_fade(1.0, 2.0),
+            )
+            .with_playlist("explore", MusicPlaylist::new("forest"))
+            .with_playlist("combat", MusicPlaylist::new("battle"));
+        context.audio.music.set_state("explore");
+
+        self.exit_handle = Some(
+            context.input.push_mapping(
+                InputMapping::default()
+                    .consume(InputConsume::Hit)
+                    .action(
+                        VirtualAction::KeyButton(VirtualKeyCode::Escape),
+                        self.exit.clone(),
+                    )
+                    .action(
+                        VirtualAction::KeyButton(VirtualKeyCode::Return),
+                        self.continue_without_gamepad.clone(),
+                    ),
             ),
-        ));
+        );
 
         self.player.activate(&mut context);
 
-        for _ in 0..6 {
+        let difficulty = context
+            .config
+            .section::<Difficulty>(DIFFICULTY_SECTION)
+            .unwrap_or_default();
+
+        for _ in 0..difficulty.enemies {
             let position = [
                 thread_rng().gen_range((-self.map_radius)..=self.map_radius),
                 thread_rng().gen_range((-self.map_radius)..=self.map_radius),
@@ -125,13 +168,15 @@ impl GameState for Gameplay {
             );
         }
 
-        for _ in 0..20 {
+        for _ in 0..difficulty.items {
             let position = [
                 thread_rng().gen_range((-self.map_radius)..=self.map_radius),
                 thread_rng().gen_range((-self.map_radius)..=self.map_radius),
             ];
-            self.items
-                .insert(ID::new(), Item::new(ItemKind::random(), position));
+            self.items.insert(
+                ID::new(),
+                Item::new(ItemKind::random(), position, context.assets),
+            );
         }
 
         self.darkness = Some(
@@ -153,28 +198,35 @@ impl GameState for Gameplay {
             self.exit_handle = None;
         }
 
-        if let Some(handle) = self.music_forest.as_mut() {
-            handle.stop(Default::default());
-        }
-        if let Some(handle) = self.music_battle.as_mut() {
-            handle.stop(Default::default());
-        }
+        context.audio.music.stop();
+
+        self.touch_controls.release();
+        context.touch.emulate_mouse = true;
     }
 
     fn fixed_update(&mut self, mut context: GameContext, delta_time: f32) {
         self.maintain(delta_time);
+        context.input_device.poll_gamepads(&self.gamepads);
 
         if self.exit.get().is_down() {
             *context.state_change = GameStateChange::Swap(Box::new(MainMenu));
         }
 
+        if self.is_gamepad_lost() {
+            self.wait_for_gamepad();
+            return;
+        }
+
+        self.touch_controls
+            .update(context.touch, context.graphics.main_camera.screen_size);
+
         self.process_game_objects(&mut context, delta_time);
 
         self.resolve_collisions();
 
         self.execute_events(&mut context);
 
-        self.update_ambient_music();
+        self.update_ambient_music(&mut context);
     }
 
     fn draw(&mut self, mut context: GameContext) {
@@ -242,6 +294,31 @@ impl GameState for Gameplay {
             )
             .draw(context.draw, context.graphics);
         }
+
+        let state = self.player.state.read().unwrap();
+        let attack = glyph_text(
+            &state.input.actions,
+            "attack",
+            context.input_device.active(),
+        )
+        .unwrap_or_else(|| context.localization.get("gameplay.tap").into_owned());
+        context
+            .localization
+            .fallback_text(
+                Text::new(ShaderRef::name("text"))
+                    .font("roboto")
+                    .size(28.0)
+                    .text(format!(
+                        "⚔ Weapon: {:?}\n☠ Enemies: {}\n★ Items: {}\n► Attack: {}",
+                        state.weapon,
+                        self.enemies.len(),
+                        self.items.len(),
+                        attack,
+                    ))
+                    .position(40.0.into())
+                    .screen_space(true),
+            )
+            .draw(context.draw, context.graphics);
     }
 
     fn draw_gui(&mut self, context: GameContext) {
@@ -274,37 +351,85 @@ impl GameState for Gameplay {
             health_bar(layout, state.health);
         }
 
-        text_box((
-            ContentBoxItemLayout {
-                margin: 40.0.into(),
-                ..Default::default()
-            },
-            TextBoxProps {
-                text: format!(
-                    "Weapon: {:?}\nEnemies: {}\nItems: {}",
-                    self.player.state.read().unwrap().weapon,
-                    self.enemies.len(),
-                    self.items.len(),
-                ),
-                font: TextBoxFont {
-                    name: "roboto".to_owned(),
-                    size: 28.0,
+        if context.touch.is_used() {
+            self.touch_controls
+                .draw_gui(context.graphics.main_camera.screen_size);
+        }
+
+        if self.is_gamepad_lost() {
+            let reconnect = context.localization.get("gameplay.reconnect").into_owned();
+            let font = context
+                .localization
+                .font("roboto", &reconnect, context.draw)
+                .to_owned();
+            text_box((
+                ContentBoxItemLayout {
+                    anchors: 0.5.into(),
+                    margin: Rect {
+                        left: -300.0,
+                        right: -300.0,
+                        top: -60.0,
+                        bottom: -60.0,
+                    },
+                    ..Default::default()
                 },
-                color: Color {
-                    r: 1.0,
-                    g: 1.0,
-                    b: 1.0,
-                    a: 1.0,
+                TextBoxProps {
+                    text: reconnect,
+                    font: TextBoxFont {
+                        name: font,
+                        size: 36.0,
+                    },
+                    horizontal_align: TextBoxHorizontalAlign::Center,
+                    color: Color {
+                        r: 1.0,
+                        g: 1.0,
+                        b: 1.0,
+                        a: 1.0,
+                    },
+                    ..Default::default()
                 },
-                ..Default::default()
-            },
-        ));
+            ));
+        }
     }
 }
 
 impl Gameplay {
+    fn is_gamepad_lost(&self) -> bool {
+        self.player
+            .state
+            .read()
+            .unwrap()
+            .input
+            .actions
+            .gamepad()
+            .map(|gamepad| gamepad.is_lost())
+            .unwrap_or_default()
+    }
+
+    /// Keeps game paused until lost gamepad is reconnected, or player decides to continue
+    /// with keyboard.
+    fn wait_for_gamepad(&mut self) {
+        let mut state = self.player.state.write().unwrap();
+        if let Some(gamepad) = state.input.actions.gamepad_mut() {
+            if self.continue_without_gamepad.get().is_pressed() {
+                gamepad.release();
+            } else {
+                gamepad.apply();
+            }
+        }
+    }
+
     fn maintain(&mut self, delta_time: f32) {
         self.gamepads.maintain();
+        for event in self.gamepads.events() {
+            if let GamepadEvent::Connected(id) | GamepadEvent::Disconnected(id) = event {
+                console_log!(
+                    "* Gamepad {:?}: {}",
+                    event,
+                    self.gamepads.gamepad_name(*id).unwrap_or_default()
+                );
+            }
+        }
         Events::maintain(delta_time);
 
         Space::write().write().unwrap().maintain(
@@ -382,13 +507,16 @@ impl Gameplay {
                     Event::PlaySound(id) => {
                         context.audio.play(id.as_ref());
                     }
+                    Event::PlaySoundAt(id, position) => {
+                        context.audio.play_at(id.as_ref(), *position);
+                    }
                     _ => {}
                 }
             }
         });
     }
 
-    fn update_ambient_music(&mut self) {
+    fn update_ambient_music(&mut self, context: &mut GameContext) {
         let player_position = self
             .player
             .state
@@ -398,30 +526,22 @@ impl Gameplay {
             .transform
             .position
             .xy();
-        let factor = self
-            .enemies
-            .values()
-            .map(|enemy| {
-                enemy
-                    .state
-                    .read()
-                    .unwrap()
-                    .sprite
-                    .transform
-                    .position
-                    .xy()
-                    .distance(player_position)
-            })
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap_or(f32::INFINITY)
-            .min(300.0) as f64
-            / 300.0;
-        if let Some(handle) = self.music_forest.as_mut() {
-            handle.set_volume(factor * 2.0, Default::default());
-        }
-        if let Some(handle) = self.music_battle.as_mut() {
-            handle.set_volume((1.0 - factor) * 2.0, Default::default());
-        }
+        let in_combat = self.enemies.values().any(|enemy| {
+            enemy
+                .state
+                .read()
+                .unwrap()
+                .sprite
+                .transform
+                .position
+                .xy()
+                .distance(player_position)
+                < 300.0
+        });
+        context
+            .audio
+            .music
+            .set_state(if in_combat { "combat" } else { "explore" });
     }
 
     fn resolve_collisions(&mut self) {
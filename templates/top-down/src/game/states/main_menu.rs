@@ -1,4 +1,4 @@
-use super::gameplay::Gameplay;
+use super::{controls::Controls, gameplay::Gameplay};
 use crate::game::{
     ui::{make_theme, text_button::text_button},
     utils::events::{Event, Events},
@@ -51,21 +51,32 @@ impl GameState for MainMenu {
                 };
 
                 text_paper(TextPaperProps {
-                    text: "RED HOOD".to_owned(),
+                    text: context.localization.get("menu.title").into_owned(),
                     variant: "title".to_owned(),
                     vertical_align_override: Some(TextBoxVerticalAlign::Bottom),
                     color_override: Some(Default::default()),
                     ..Default::default()
                 });
 
-                let new_game = text_button(button_props.clone(), "New Game");
+                let new_game = text_button(
+                    button_props.clone(),
+                    context.localization.get("menu.new-game"),
+                );
                 if new_game.trigger_stop() {
                     *context.state_change = GameStateChange::Swap(Box::<Gameplay>::default());
                 }
 
+                let controls = text_button(
+                    button_props.clone(),
+                    context.localization.get("menu.controls"),
+                );
+                if controls.trigger_stop() {
+                    *context.state_change = GameStateChange::Swap(Box::<Controls>::default());
+                }
+
                 #[cfg(not(target_arch = "wasm32"))]
                 {
-                    let exit = text_button(button_props, "Exit");
+                    let exit = text_button(button_props, context.localization.get("menu.exit"));
                     if exit.trigger_stop() {
                         *context.state_change = GameStateChange::Pop;
                     }
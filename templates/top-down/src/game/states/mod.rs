@@ -1,3 +1,4 @@
+pub mod controls;
 pub mod game_end;
 pub mod gameplay;
 pub mod main_menu;
@@ -1,6 +1,6 @@
 use super::main_menu::MainMenu;
 use micro_games_kit::{
-    assets::shader::ShaderAsset,
+    assets::{font::FontFallbackChain, shader::ShaderAsset},
     context::GameContext,
     game::{GameState, GameStateChange},
     third_party::{
@@ -44,8 +44,10 @@ impl GameState for Preloader {
     fn enter(&mut self, mut context: GameContext) {
         Self::load_shaders(&mut context);
         Self::load_fonts(&mut context);
+        Self::load_locales(&mut context);
         Self::load_textures(&mut context);
         Self::load_sounds_and_music(&mut context);
+        Self::load_gamepad_mappings(&mut context);
         Self::setup_gui_inputs(&mut context);
 
         *context.state_change = GameStateChange::Swap(Box::new(MainMenu));
@@ -100,6 +102,19 @@ impl Preloader {
             .assets
             .ensure("font://fonts/roboto.ttf?as=roboto")
             .unwrap();
+        context
+            .assets
+            .ensure("font://fonts/dejavu-sans-mono.ttf?as=symbols")
+            .unwrap();
+        context.localization.font_fallbacks.insert(
+            "roboto".to_owned(),
+            FontFallbackChain::new("roboto").with_fallback("symbols"),
+        );
+    }
+
+    fn load_locales(context: &mut GameContext) {
+        context.assets.ensure("locale://locales/en.toml").unwrap();
+        context.assets.ensure("locale://locales/pl.toml").unwrap();
     }
 
     fn load_textures(context: &mut GameContext) {
@@ -203,6 +218,10 @@ impl Preloader {
             .assets
             .ensure("sound://sounds/sword.ogg?as=sword")
             .unwrap();
+        context
+            .assets
+            .ensure("sound-group://sounds/groups.toml")
+            .unwrap();
         context
             .assets
             .ensure("sound://sounds/axe.ogg?as=axe")
@@ -222,11 +241,18 @@ impl Preloader {
 
         context
             .assets
-            .ensure("sound://music/forest.ogg?as=forest")
+            .ensure("music://music/forest.ogg?as=forest")
             .unwrap();
         context
             .assets
-            .ensure("sound://music/battle.ogg?as=battle")
+            .ensure("music://music/battle.ogg?as=battle")
+            .unwrap();
+    }
+
+    fn load_gamepad_mappings(context: &mut GameContext) {
+        context
+            .assets
+            .ensure("gamepads://gamepads/gamecontrollerdb.txt")
             .unwrap();
     }
 
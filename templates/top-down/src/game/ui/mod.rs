@@ -1,9 +1,9 @@
 pub mod health_bar;
 pub mod text_button;
 
-use super::utils::world_to_screen_anchor;
 use micro_games_kit::{
     context::GameContext,
+    input::picking::world_to_screen_anchor,
     third_party::{
         raui_immediate_widgets::{
             core::{
@@ -24,7 +24,7 @@ pub fn world_to_screen_content_layout(
     region: Rect,
     context: &GameContext,
 ) -> ContentBoxItemLayout {
-    let anchor = world_to_screen_anchor(position, context);
+    let anchor = world_to_screen_anchor(context.graphics, position);
     ContentBoxItemLayout {
         anchors: Rect {
             left: anchor.x,
@@ -30,6 +30,7 @@ pub enum Event {
     },
     WinGame,
     PlaySound(Cow<'static, str>),
+    PlaySoundAt(Cow<'static, str>, Vec2<f32>),
 }
 
 #[derive(Default)]